@@ -1,11 +1,14 @@
 use std::io;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
 use clap::{Arg, ArgAction, Command, value_parser};
-use clap::builder::PossibleValue;
+use clap::builder::{PossibleValue, PossibleValuesParser};
 use ipnetwork::Ipv4Network;
 use pnet::datalink::NetworkInterface;
 use termcolor::Color;
 
+use crate::options::CsvColumn;
 use crate::tools::{print_formatted_std_error, print_formatted_std_output};
 
 const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -16,8 +19,13 @@ pub fn build_command() -> Command {
         .version(CLI_VERSION)
         .about("A ARP scan tool written in Rust for learning purposes.")
         .author("Andrew <dr.chrom@gmail.com>")
+        .subcommand(
+            Command::new("interfaces")
+                .about("List the network interfaces available for a scan, in the format given by --output")
+        )
         .arg(
             Arg::new("output").short('o').long("output")
+                .global(true)
                 .default_value("plain")
                 .value_name("FORMAT")
                 .default_value("plain")
@@ -25,9 +33,49 @@ pub fn build_command() -> Command {
                     PossibleValue::new("plain").help("Verbose output with table"),
                     PossibleValue::new("json").help("JSON format"),
                     PossibleValue::new("yaml").help("YAML format"),
-                    PossibleValue::new("csv").help("CSV format")
+                    PossibleValue::new("csv").help("CSV format"),
+                    PossibleValue::new("ndjson").help("One JSON event per line, streamed during the scan"),
+                    PossibleValue::new("xml").help("nmap-compatible XML format")
                 ])
-                .help("Define output format {plain, json, yaml, csv}")
+                .help("Define output format {plain, json, yaml, csv, ndjson, xml}")
+        )
+        .arg(
+            Arg::new("columns").long("columns")
+                .value_name("COLUMNS")
+                .value_delimiter(',')
+                .value_parser(PossibleValuesParser::new(CsvColumn::NAMES))
+                .help("Comma-separated list of CSV columns (default: ip,mac,vendor,rtt)")
+        )
+        .arg(
+            Arg::new("no_header").long("no-header")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Do not write the CSV header row")
+        )
+        .arg(
+            Arg::new("delimiter").long("delimiter")
+                .global(true)
+                .value_name("CHAR")
+                .help("CSV field delimiter, 'tab' for a tabulation (default: ,)")
+        )
+        .arg(
+            Arg::new("oui_file").long("oui-file")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("File mapping MAC address prefixes to vendors (default: /usr/share/arp-scan/ieee-oui.txt)")
+        )
+        .arg(
+            Arg::new("pcap").long("pcap")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write every frame sent and received during the scan to a pcap file")
+        )
+        .arg(
+            Arg::new("stats").long("stats")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("With --output csv, also write the scan statistics (loss, duplicates, RTT, rate, duration) \
+                       to this CSV file")
         )
         .arg(
             Arg::new("profile").short('p').long("profile")
@@ -37,7 +85,7 @@ pub fn build_command() -> Command {
                     PossibleValue::new("default").help("Default scan profile"),
                     PossibleValue::new("fast").help("Fast ARP scans (less accurate)"),
                     PossibleValue::new("stealth").help("Slower scans (minimize impact)"),
-                    PossibleValue::new("chaos").help("Randomly-selected values")
+                    PossibleValue::new("chaos").help("Randomly-selected values (replay with --seed)")
                 ])
                 .help("Scan profile - a preset of ARP scan options")
         )
@@ -49,11 +97,191 @@ pub fn build_command() -> Command {
                 .value_name("NETWORK_RANGE")
                 .value_parser(value_parser!(Ipv4Network))
                 .value_parser(clap::builder::NonEmptyStringValueParser::new())
-                .required(true)
-                .help("Provides an input network interface (example: --network 192.168.0.0/24)")
+                .help("Provides an input network interface (example: --network 192.168.0.0/24, default: \
+                       the subnets attached to the selected interface, the ones larger than /16 skipped)")
+        )
+        .arg(
+            Arg::new("allow_large_network")
+                .long("allow-large-network")
+                .action(ArgAction::SetTrue)
+                .help("Allow scanning networks larger than /16, every target is kept in memory")
+        )
+        .arg(
+            Arg::new("include_interface")
+                .long("include-interface")
+                .global(true)
+                .value_name("PATTERNS")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Only list and use interfaces whose name matches one of these glob patterns (example: eth*,wlan*)")
+        )
+        .arg(
+            Arg::new("exclude_interface")
+                .long("exclude-interface")
+                .global(true)
+                .value_name("PATTERNS")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Never list nor use interfaces whose name matches one of these glob patterns (example: docker*,veth*)")
+        )
+        .arg(
+            Arg::new("show_all_interfaces")
+                .long("show-all-interfaces")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Also list interfaces that are down, have no IPv4 address or cannot carry ARP")
+        )
+        .arg(
+            Arg::new("hide_virtual")
+                .long("hide-virtual")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Hide virtual interfaces (bridges, veth pairs, tunnels...) that have no underlying device")
+        )
+        .arg(
+            Arg::new("source_ip")
+                .long("source-ip")
+                .value_name("IPV4")
+                .value_parser(value_parser!(Ipv4Addr))
+                .help("Source IPv4 address of the ARP requests, only when scanning from a single interface \
+                       (default: the interface address in the scanned network)")
+        )
+        .arg(
+            Arg::new("interface")
+                .short('I')
+                .long("interface")
+                .value_name("INTERFACES")
+                .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Network interfaces to scan from, by name or by index in the interface list, each one optionally \
+                       with its own network (example: --interface eth0 or --interface eth1=10.0.1.0/24,eth2)")
+        )
+        .arg(
+            Arg::new("all_interfaces")
+                .long("all-interfaces")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["interface", "network"])
+                .help("Scan the subnets of every available interface at the same time")
+        )
+        .arg(
+            Arg::new("interval").short('i').long("interval")
+                .value_name("MILLISECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Minimal delay between two ARP requests (overrides the profile)")
+        )
+        .arg(
+            Arg::new("max_interval").long("max-interval")
+                .value_name("MILLISECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Maximal delay between two ARP requests, each gap is random in between (overrides the profile)")
+        )
+        .arg(
+            Arg::new("retry").short('r').long("retry")
+                .value_name("COUNT")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Total number of ARP requests sent to a host that does not answer (overrides the profile)")
+        )
+        .arg(
+            Arg::new("random_retry").long("random-retry")
+                .action(ArgAction::SetTrue)
+                .help("Pick the number of ARP requests of every host at random up to --retry")
+        )
+        .arg(
+            Arg::new("no_random_retry").long("no-random-retry")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("random_retry")
+                .help("Send --retry ARP requests to every host, even with the chaos profile")
+        )
+        .arg(
+            Arg::new("timeout").short('t').long("timeout")
+                .value_name("MILLISECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Time to wait for a reply before the first retransmission (overrides the profile)")
+        )
+        .arg(
+            Arg::new("backoff").short('b').long("backoff")
+                .value_name("FACTOR")
+                .value_parser(value_parser!(f64))
+                .help("Factor applied to the timeout after every unanswered request (overrides the profile)")
+        )
+        .arg(
+            Arg::new("wait").short('w').long("wait")
+                .value_name("MILLISECONDS")
+                .value_parser(value_parser!(u64))
+                .help("Time to wait for replies after the last ARP request is sent (overrides the profile)")
+        )
+        .arg(
+            Arg::new("order").long("order")
+                .value_name("ORDER")
+                .value_parser([
+                    PossibleValue::new("sequential").help("Probe hosts in ascending address order"),
+                    PossibleValue::new("random").help("Probe hosts in random order")
+                ])
+                .help("Order in which hosts are probed (overrides the profile)")
+        )
+        .arg(
+            Arg::new("concurrency").short('c').long("concurrency")
+                .value_name("COUNT")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Maximum number of hosts waiting for a reply at the same time (overrides the profile)")
+        )
+        .arg(
+            Arg::new("rate").long("rate")
+                .value_name("PACKETS_PER_SECOND")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Maximum number of ARP requests sent per second on each interface, scanning from \
+                       several interfaces multiplies the total")
+        )
+        .arg(
+            Arg::new("bandwidth").long("bandwidth")
+                .value_name("BITS_PER_SECOND")
+                .value_parser(parse_bandwidth)
+                .help("Maximum bandwidth used by ARP requests on each interface, with an optional K, M or G suffix \
+                       (example: --bandwidth 256K), scanning from several interfaces multiplies the total")
+        )
+        .arg(
+            Arg::new("unsolicited").long("unsolicited")
+                .action(ArgAction::SetTrue)
+                .help("Also report ARP replies from hosts outside of the scanned network")
+        )
+        .arg(
+            Arg::new("seed").long("seed")
+                .value_name("SEED")
+                .value_parser(value_parser!(u64))
+                .help("Seed of the random choices, to replay a randomized scan exactly")
         )
 }
 
+/// Parses a bandwidth in bits per second with an optional `K`, `M` or `G` suffix.
+///
+/// # Parameters
+///
+/// - `value`: The value given with `--bandwidth`, e.g. `64000` or `256K`.
+///
+/// # Returns
+///
+/// A `Result` containing the bandwidth in bits per second, or a `String` error message
+/// if the value is not a positive number.
+fn parse_bandwidth(value: &str) -> Result<u64, String> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&value[..index], 1_000),
+        Some((index, 'm' | 'M')) => (&value[..index], 1_000_000),
+        Some((index, 'g' | 'G')) => (&value[..index], 1_000_000_000),
+        _ => (value, 1),
+    };
+    let bandwidth = number
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse bandwidth: {}", e))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| String::from("Bandwidth is too large"))?;
+
+    if bandwidth == 0 {
+        return Err(String::from("Bandwidth must be greater than 0"));
+    }
+    Ok(bandwidth)
+}
+
 /// Retrieves the target IP address from the command-line arguments.
 ///
 /// This function takes an iterator over command-line arguments and attempts to extract
@@ -87,6 +315,7 @@ pub fn build_command() -> Command {
 ///         }
 ///     }
 /// ```
+#[allow(dead_code)]
 pub fn get_target_ip_from_args(
     mut args: impl Iterator<Item=String>,
 ) -> Result<Ipv4Network, String> {
@@ -102,8 +331,9 @@ pub fn get_target_ip_from_args(
 /// Prompts the user to select a network interface and returns the selected interface index.
 ///
 /// This function takes a vector of references to `NetworkInterface` instances and prompts
-/// the user to select an interface by entering the corresponding number. It returns the
-/// index of the selected interface if the input is valid.
+/// the user to select an interface by entering its kernel index, as listed by
+/// `view::plain::show_list_interfaces`. It returns the position of the selected interface
+/// in `interfaces` if the input is valid.
 ///
 /// # Parameters
 ///
@@ -111,7 +341,7 @@ pub fn get_target_ip_from_args(
 ///
 /// # Returns
 ///
-/// A `Result` containing the selected interface index if successful, or an `std::io::Error`
+/// A `Result` containing the position of the selected interface if successful, or an `std::io::Error`
 /// if there is an issue reading from the standard input.
 ///
 /// # Examples
@@ -133,7 +363,7 @@ pub fn get_target_ip_from_args(
 ///     }
 /// }
 /// ```
-pub fn prompt_for_interface(interfaces: &Vec<&NetworkInterface>) -> Result<usize, std::io::Error> {
+pub fn prompt_for_interface(interfaces: &[&NetworkInterface]) -> Result<usize, std::io::Error> {
     loop {
         print_formatted_std_output(
             String::from("Please select the interface to use: "),
@@ -143,9 +373,9 @@ pub fn prompt_for_interface(interfaces: &Vec<&NetworkInterface>) -> Result<usize
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
 
-        if let Ok(interface_number) = input.trim().parse::<usize>() {
-            if interface_number < interfaces.len() {
-                return Result::Ok(interface_number);
+        if let Ok(interface_number) = input.trim().parse::<u32>() {
+            if let Some(position) = interfaces.iter().position(|interface| interface.index == interface_number) {
+                return Result::Ok(position);
             } else {
                 print_formatted_std_error(
                     String::from("Invalid interface number. Please enter a valid number: "),
@@ -162,6 +392,7 @@ pub fn prompt_for_interface(interfaces: &Vec<&NetworkInterface>) -> Result<usize
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use std::net::Ipv4Addr;
 
@@ -223,4 +454,19 @@ mod tests {
             Ipv4Network::new(Ipv4Addr::new(192, 168, 0, 1), 24).unwrap()
         );
     }
+
+    #[test]
+    fn test_parse_bandwidth_suffixes() {
+        assert_eq!(parse_bandwidth("64000"), Ok(64_000));
+        assert_eq!(parse_bandwidth("256K"), Ok(256_000));
+        assert_eq!(parse_bandwidth("2m"), Ok(2_000_000));
+        assert_eq!(parse_bandwidth("1G"), Ok(1_000_000_000));
+    }
+
+    #[test]
+    fn test_parse_bandwidth_invalid() {
+        assert_eq!(parse_bandwidth("0"), Err(String::from("Bandwidth must be greater than 0")));
+        assert!(parse_bandwidth("fast").is_err());
+        assert!(parse_bandwidth("K").is_err());
+    }
 }
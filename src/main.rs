@@ -1,23 +1,57 @@
 extern crate interfaces;
 
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal};
 use std::process;
+use std::sync::Mutex;
+use std::thread;
+
+use ipnetwork::Ipv4Network;
 
 use nix::unistd::Uid;
 use pnet::datalink;
 use termcolor::Color;
 
+use report::ScanObserver;
 use tools::{check_supported_os, print_formatted_std_error};
 
 mod cli;
 mod net;
 mod tools;
 mod options;
+mod pcap;
+mod probe;
+mod report;
+mod route;
+mod stats;
+mod throttle;
+mod transport;
+mod vendor;
 
 mod view {
+    pub mod csv;
+    pub mod document;
+    pub mod json;
+    pub mod ndjson;
     pub mod plain;
+    pub mod xml;
+    pub mod yaml;
 }
 
 fn main() {
+    let command = cli::build_command().get_matches();
+    if let Some(matches) = command.subcommand_matches("interfaces") {
+        // `--columns` is not global, but can still be given before the subcommand.
+        if command.get_many::<String>("columns").is_some() {
+            print_formatted_std_error(
+                String::from("--columns only applies to scans, the interface listing has fixed columns"),
+                None,
+            );
+            process::exit(exitcode::USAGE);
+        }
+        list_interfaces(matches);
+    }
+
     if !Uid::effective().is_root() {
         print_formatted_std_error(
             String::from("You must be root privilege to run this program"),
@@ -31,30 +65,345 @@ fn main() {
         process::exit(exitcode::OSERR);
     });
 
-    let command = cli::build_command().get_matches();
     let scan_options = options::CliOptions::new(&command).unwrap_or_else(|e| {
         print_formatted_std_error(e.to_string(), None);
         process::exit(exitcode::USAGE);
     });
 
+    if scan_options.timing.is_randomized() {
+        print_formatted_std_error(
+            format!("Random seed: {0} (replay with --seed {0})", scan_options.seed),
+            Some(Color::Yellow),
+        );
+    }
+
     let binding = datalink::interfaces();
 
-    // Get list of available network interfaces
-    let interfaces = net::get_available_interfaces(&binding);
+    // Get list of the network interfaces selected by the filters
+    let interfaces = net::filter_interfaces(&binding, &scan_options.interface_filter);
+    let candidates: Vec<&datalink::NetworkInterface> = interfaces
+        .iter()
+        .copied()
+        .filter(|interface| net::get_unavailable_reason(interface).is_none())
+        .collect();
 
-    view::plain::show_list_interfaces(&interfaces).unwrap_or_else(|e| {
-        print_formatted_std_error(e.to_string(), None);
-        process::exit(exitcode::UNAVAILABLE);
+    let selected: Vec<(&datalink::NetworkInterface, Option<Ipv4Network>)> = if scan_options.all_interfaces {
+        if candidates.is_empty() {
+            print_formatted_std_error(String::from("No available interface to scan"), None);
+            process::exit(exitcode::UNAVAILABLE);
+        }
+        candidates.iter().map(|interface| (*interface, None)).collect()
+    } else if !scan_options.interfaces.is_empty() {
+        scan_options
+            .interfaces
+            .iter()
+            .map(|selection| (find_selected_interface(&binding, &interfaces, &selection.selector), selection.network))
+            .collect()
+    } else {
+        vec![(pick_interface(&interfaces, &candidates, scan_options.network.as_ref()), None)]
+    };
+
+    for (interface, _) in &selected {
+        if let Some(reason) = net::get_unavailable_reason(interface) {
+            print_formatted_std_error(reason, None);
+            process::exit(exitcode::USAGE);
+        }
+    }
+
+    // An interface scans the network given for it, else --network, else its own subnets up to
+    // /MIN_NETWORK_PREFIX. An interface selected several times (by name and by index, or with
+    // several networks) gets a single job, its networks scanned one after the other.
+    let mut jobs: Vec<(&datalink::NetworkInterface, Vec<Ipv4Network>)> = Vec::new();
+    for (position, (interface, network)) in selected.iter().enumerate() {
+        if selected[..position].contains(&(*interface, *network)) {
+            continue;
+        }
+        let networks = match network.or(scan_options.network) {
+            Some(network) => vec![network],
+            None => get_local_networks(interface, scan_options.allow_large_network),
+        };
+
+        let job = match jobs.iter().position(|(job, _)| job.name == interface.name) {
+            Some(job) => job,
+            None => {
+                jobs.push((interface, Vec::new()));
+                jobs.len() - 1
+            }
+        };
+        for network in networks {
+            if !jobs[job].1.contains(&network) {
+                jobs[job].1.push(network);
+            }
+        }
+    }
+    jobs.retain(|(_, networks)| !networks.is_empty());
+    if jobs.is_empty() {
+        print_formatted_std_error(String::from("No network to scan, use --network"), None);
+        process::exit(exitcode::USAGE);
+    }
+    // A source address is only valid on one link, the others would get foreign requests.
+    if scan_options.source_ip.is_some() && jobs.len() > 1 {
+        print_formatted_std_error(
+            String::from("--source-ip can only be used when scanning from a single interface"),
+            None,
+        );
+        process::exit(exitcode::USAGE);
+    }
+
+    let vendors = match &scan_options.oui_file {
+        Some(path) => vendor::VendorDatabase::load(path).unwrap_or_else(|e| {
+            print_formatted_std_error(format!("Failed to read {}: {}", path.display(), e), None);
+            process::exit(exitcode::NOINPUT);
+        }),
+        None => vendor::VendorDatabase::load_default(),
+    };
+
+    let capture = scan_options.pcap.as_ref().map(|path| {
+        pcap::PcapWriter::create(path).unwrap_or_else(|e| {
+            print_formatted_std_error(format!("Failed to create {}: {}", path.display(), e), None);
+            process::exit(exitcode::CANTCREAT);
+        })
+    });
+    let mut stats_file = scan_options.stats.as_ref().map(|path| {
+        File::create(path).map(BufWriter::new).unwrap_or_else(|e| {
+            print_formatted_std_error(format!("Failed to create {}: {}", path.display(), e), None);
+            process::exit(exitcode::CANTCREAT);
+        })
     });
+    let events = match scan_options.output {
+        options::OutputFormat::Ndjson => Some(Mutex::new(view::ndjson::NdjsonWriter::new(io::stdout(), &vendors))),
+        _ => None,
+    };
+
+    // Every interface is scanned in its own thread, its networks one after the other. Each
+    // thread has its own --rate and --bandwidth limits: they cap the load of one link.
+    let results: Vec<(&str, Result<Vec<report::ScanReport>, io::Error>)> = thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .iter()
+            .map(|(interface, networks)| {
+                let (scan_options, capture, events) = (&scan_options, &capture, &events);
+                scope.spawn(move || scan_interface(interface, networks, scan_options, capture.clone(), events))
+            })
+            .collect();
+
+        jobs.iter()
+            .zip(handles)
+            .map(|((interface, _), handle)| {
+                let scanned = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("The scan stopped unexpectedly")));
+                (interface.name.as_str(), scanned)
+            })
+            .collect()
+    });
+
+    let mut reports = Vec::new();
+    let mut failures = Vec::new();
+    for (name, scanned) in results {
+        match scanned {
+            Ok(scanned) => reports.extend(scanned),
+            Err(e) => failures.push(format!("Failed to scan {}: {}", name, e)),
+        }
+    }
+    let Some(mut report) = report::ScanReport::merge(reports) else {
+        for failure in failures {
+            print_formatted_std_error(failure, None);
+        }
+        process::exit(exitcode::UNAVAILABLE);
+    };
+    for failure in failures {
+        print_formatted_std_error(failure, Some(Color::Yellow));
+    }
+    vendors.annotate(&mut report);
 
-    let selected_interface = cli::prompt_for_interface(&interfaces).unwrap_or_else(|e| {
+    let shown = match scan_options.output {
+        options::OutputFormat::Json => view::json::show_scan_report(&report),
+        options::OutputFormat::Yaml => view::yaml::show_scan_report(&report),
+        options::OutputFormat::Csv => view::csv::show_scan_report(&report, &scan_options.csv),
+        options::OutputFormat::Xml => view::xml::show_scan_report(&report),
+        options::OutputFormat::Plain => view::plain::show_scan_report(&report),
+        // Every other event was already written while scanning.
+        options::OutputFormat::Ndjson => match events {
+            Some(events) => events
+                .into_inner()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .on_event(&report::ScanEvent::Finished(&report)),
+            None => Ok(()),
+        },
+    };
+    let shown = shown.and_then(|_| match &mut stats_file {
+        Some(stats_file) => view::csv::write_statistics(stats_file, &report, &scan_options.csv),
+        None => Ok(()),
+    });
+    shown.unwrap_or_else(|e| {
         print_formatted_std_error(e.to_string(), None);
+        process::exit(exitcode::IOERR);
+    });
+
+    process::exit(exitcode::OK);
+}
+
+/// Returns the interface given with `--interface`, exiting when it cannot be scanned.
+fn find_selected_interface<'a>(
+    binding: &[datalink::NetworkInterface],
+    interfaces: &[&'a datalink::NetworkInterface],
+    selector: &str,
+) -> &'a datalink::NetworkInterface {
+    match net::find_interface(interfaces, selector) {
+        Ok(position) => interfaces[position],
+        Err(e) => {
+            let unavailable = binding
+                .iter()
+                .find(|interface| interface.name == selector)
+                .and_then(net::get_unavailable_reason);
+            print_formatted_std_error(unavailable.unwrap_or(e), None);
+            process::exit(exitcode::USAGE);
+        }
+    }
+}
+
+/// Picks the interface to scan `network` (or its own subnets) from, asking the user when it
+/// is ambiguous and stdin is a terminal.
+fn pick_interface<'a>(
+    interfaces: &[&'a datalink::NetworkInterface],
+    candidates: &[&'a datalink::NetworkInterface],
+    network: Option<&Ipv4Network>,
+) -> &'a datalink::NetworkInterface {
+    let routes = route::read_routes().unwrap_or_default();
+    let picked = match network {
+        Some(network) => net::pick_interface(candidates, network, &routes),
+        None => net::pick_default_interface(candidates, &routes),
+    };
+    match picked {
+        Ok(position) => candidates[position],
+        Err(e) if io::stdin().is_terminal() => {
+            print_formatted_std_error(e, Some(Color::Yellow));
+            view::plain::show_list_interfaces(interfaces).unwrap_or_else(|e| {
+                print_formatted_std_error(e.to_string(), None);
+                process::exit(exitcode::UNAVAILABLE);
+            });
+
+            let position = cli::prompt_for_interface(interfaces).unwrap_or_else(|e| {
+                print_formatted_std_error(e.to_string(), None);
+                process::exit(exitcode::USAGE);
+            });
+            interfaces[position]
+        }
+        Err(e) => {
+            print_formatted_std_error(e, None);
+            process::exit(exitcode::USAGE);
+        }
+    }
+}
+
+/// Returns the subnets of `interface` to scan without `--network`, warning about the ones
+/// too large to scan unless `allow_large_network` is set.
+fn get_local_networks(interface: &datalink::NetworkInterface, allow_large_network: bool) -> Vec<Ipv4Network> {
+    let (networks, too_large): (Vec<Ipv4Network>, Vec<Ipv4Network>) = net::get_interface_networks(interface)
+        .into_iter()
+        .partition(|network| allow_large_network || network.prefix() >= options::MIN_NETWORK_PREFIX);
+    for network in too_large {
+        print_formatted_std_error(
+            format!(
+                "Skipping {} on {}: larger than /{}, pass --allow-large-network to scan it",
+                network,
+                interface.name,
+                options::MIN_NETWORK_PREFIX
+            ),
+            Some(Color::Yellow),
+        );
+    }
+    networks
+}
+
+type SharedNdjsonWriter<'a> = Mutex<view::ndjson::NdjsonWriter<'a, io::Stdout>>;
+
+/// Scans each of `networks` from `interface`, recording frames into `capture` and streaming
+/// discoveries and retries to `events`.
+///
+/// The final event is left out: it is written once for the merged report of all interfaces.
+fn scan_interface(
+    interface: &datalink::NetworkInterface,
+    networks: &[Ipv4Network],
+    scan_options: &options::CliOptions,
+    capture: Option<pcap::PcapWriter<pcap::SharedWriter<BufWriter<File>>>>,
+    events: &Option<SharedNdjsonWriter>,
+) -> Result<Vec<report::ScanReport>, io::Error> {
+    let transport = transport::PnetTransport::open(interface)?;
+    match capture {
+        Some(capture) => {
+            let mut transport = pcap::CaptureTransport::new(transport, capture);
+            let scanned = scan_networks(&mut transport, networks, scan_options, events);
+            let flushed = transport.finish();
+            scanned.and_then(|reports| flushed.map(|_| reports))
+        }
+        None => {
+            let mut transport = transport;
+            scan_networks(&mut transport, networks, scan_options, events)
+        }
+    }
+}
+
+/// Scans `networks` one after the other through the same transport, streaming the events
+/// of every scan to `events` when the output is NDJSON.
+fn scan_networks<T: transport::Transport>(
+    transport: &mut T,
+    networks: &[Ipv4Network],
+    scan_options: &options::CliOptions,
+    events: &Option<SharedNdjsonWriter>,
+) -> Result<Vec<report::ScanReport>, io::Error> {
+    networks
+        .iter()
+        .map(|network| {
+            let options = options::CliOptions {
+                network: Some(*network),
+                ..scan_options.clone()
+            };
+            match events {
+                Some(events) => {
+                    let mut observer = |event: &report::ScanEvent| match event {
+                        report::ScanEvent::Finished(_) => Ok(()),
+                        _ => events
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .on_event(event),
+                    };
+                    net::arp_scan_with_observer(transport, &options, &mut observer)
+                }
+                None => net::arp_scan(transport, &options),
+            }
+        })
+        .collect()
+}
+
+/// Runs the `interfaces` subcommand: lists the available interfaces in the requested format.
+fn list_interfaces(matches: &clap::ArgMatches) -> ! {
+    let list_options = options::ListOptions::new(matches).unwrap_or_else(|e| {
+        print_formatted_std_error(e, None);
         process::exit(exitcode::USAGE);
     });
 
-    net::arp_scan(interfaces[selected_interface], &scan_options).unwrap_or_else(|e| {
+    let binding = datalink::interfaces();
+    let interfaces = net::filter_interfaces(&binding, &list_options.interface_filter);
+    let records: Vec<view::document::InterfaceRecord> = interfaces
+        .iter()
+        .map(|interface| view::document::InterfaceRecord::from(*interface))
+        .collect();
+
+    let shown = match list_options.output {
+        options::OutputFormat::Plain => view::plain::show_list_interfaces(&interfaces),
+        options::OutputFormat::Json => view::json::show_interfaces(&records),
+        options::OutputFormat::Yaml => view::yaml::show_interfaces(&records),
+        options::OutputFormat::Csv => view::csv::show_interfaces(&records, &list_options.csv),
+        options::OutputFormat::Ndjson => view::ndjson::show_interfaces(&records),
+        options::OutputFormat::Xml => {
+            print_formatted_std_error(String::from("The xml output is only available for scans"), None);
+            process::exit(exitcode::USAGE);
+        }
+    };
+    shown.unwrap_or_else(|e| {
         print_formatted_std_error(e.to_string(), None);
-        process::exit(exitcode::UNAVAILABLE);
+        process::exit(exitcode::IOERR);
     });
 
     process::exit(exitcode::OK);
@@ -1,21 +1,37 @@
-use std::io::Error;
-use std::net::{IpAddr, Ipv4Addr};
-use std::time::Duration;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::net::Ipv4Addr;
+use std::time::{Instant, SystemTime};
 
-use pnet::datalink::{Channel, Config, DataLinkReceiver, MacAddr, NetworkInterface};
+use ipnetwork::{IpNetwork, Ipv4Network};
+use pnet::datalink::{MacAddr, NetworkInterface};
 use pnet::packet::{MutablePacket, Packet};
 use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
 use pnet::packet::ethernet::{EthernetPacket, EtherTypes, MutableEthernetPacket};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
-use crate::options::CliOptions;
+use crate::options::{CliOptions, InterfaceFilter, TargetOrder};
+use crate::probe::{ProbeScheduler, ReplyMatch};
+use crate::report::{DiscoveredHost, ScanEvent, ScanObserver, ScanReport};
+use crate::route::{self, Route};
+use crate::throttle::SendLimiter;
+use crate::transport::Transport;
 
-pub const DATALINK_RCV_TIMEOUT: u64 = 500;
+/// Size of the ARP request built by `build_arp_packet`.
+pub const ARP_FRAME_LENGTH: usize = 42;
+
+/// Size of the ARP request on the wire, once padded to the minimal Ethernet frame.
+pub const ARP_WIRE_FRAME_LENGTH: usize = 60;
 
 
 /// Returns a vector of references to available network interfaces.
 ///
 /// This function takes a reference to a vector of `NetworkInterface` instances and
-/// filters out interfaces that are not up, are loopback, or do not have any IPv4 addresses.
+/// filters out interfaces that are not up, are loopback, do not have any IPv4 addresses, or
+/// cannot carry ARP (see `check_ethernet_link`).
 /// ARP (Address Resolution Protocol) is used to map a known IP address to a MAC (Media Access Control) address in IPv4 networks.
 /// For IPv6, the equivalent protocol is NDP (Neighbor Discovery Protocol).
 /// NDP serves the same purpose as ARP but is designed specifically for IPv6.
@@ -41,86 +57,593 @@ pub const DATALINK_RCV_TIMEOUT: u64 = 500;
 ///     println!("Available Interface: {}", interface.name);
 /// }
 /// ```
-pub fn get_available_interfaces(all_interfaces: &Vec<NetworkInterface>) -> Vec<&NetworkInterface> {
+pub fn get_available_interfaces(all_interfaces: &[NetworkInterface]) -> Vec<&NetworkInterface> {
     all_interfaces
         .iter()
-        .filter(|interface| interface.is_up() && !interface.is_loopback())
-        .filter(|interface| interface.ips.iter().any(|ip| ip.is_ipv4()))
+        .filter(|interface| get_unavailable_reason(interface).is_none())
+        .collect()
+}
+
+/// Explains why `interface` cannot be scanned from, or returns `None` if it can.
+pub fn get_unavailable_reason(interface: &NetworkInterface) -> Option<String> {
+    if !interface.is_up() {
+        return Some(format!("Interface {} is down", interface.name));
+    }
+    if interface.is_loopback() {
+        return Some(format!("Interface {} is a loopback interface", interface.name));
+    }
+    if !interface.ips.iter().any(|ip| ip.is_ipv4()) {
+        return Some(format!("Interface {} has no IPv4 address", interface.name));
+    }
+    check_ethernet_link(interface).err().map(|e| e.to_string())
+}
+
+/// Returns the interfaces selected by `filter`.
+///
+/// Only available interfaces (see `get_available_interfaces`) are kept unless
+/// `filter.show_all` is set. Names must then match one of the `include` glob patterns, if
+/// any, and none of the `exclude` ones. With `hide_virtual`, interfaces without an
+/// underlying device (see `is_virtual_interface`) are dropped.
+pub fn filter_interfaces<'a>(
+    all_interfaces: &'a [NetworkInterface],
+    filter: &InterfaceFilter,
+) -> Vec<&'a NetworkInterface> {
+    let listed = if filter.show_all {
+        all_interfaces.iter().collect()
+    } else {
+        get_available_interfaces(all_interfaces)
+    };
+
+    listed
+        .into_iter()
+        .filter(|interface| {
+            filter.include.is_empty() || filter.include.iter().any(|pattern| glob_match(pattern, &interface.name))
+        })
+        .filter(|interface| !filter.exclude.iter().any(|pattern| glob_match(pattern, &interface.name)))
+        .filter(|interface| !filter.hide_virtual || !is_virtual_interface(&interface.name))
+        .collect()
+}
+
+/// Returns `true` if the interface called `name` is not backed by a device (bridges,
+/// veth pairs, tunnels, ...), i.e. `/sys/class/net/<name>/device` does not exist.
+pub fn is_virtual_interface(name: &str) -> bool {
+    !Path::new("/sys/class/net").join(name).join("device").exists()
+}
+
+/// Matches `name` against a glob `pattern`, where `*` matches any sequence of characters
+/// and `?` any single character.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// `IFF_NOARP`: the interface does not use ARP.
+const IFF_NOARP: u32 = 0x80;
+
+/// Why an interface cannot be used for an ARP scan.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceError {
+    /// The interface has no hardware address (tun, WireGuard, PPP, ...).
+    NoMacAddress(String),
+    /// The interface is a point-to-point or ARP-less link.
+    NotEthernet(String),
+}
+
+impl fmt::Display for InterfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterfaceError::NoMacAddress(name) => write!(
+                f,
+                "Interface {} has no MAC address, ARP needs an Ethernet-like link",
+                name
+            ),
+            InterfaceError::NotEthernet(name) => write!(
+                f,
+                "Interface {} is not an Ethernet-like link (point-to-point or without ARP), ARP cannot be used on it",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InterfaceError {}
+
+impl From<InterfaceError> for Error {
+    fn from(error: InterfaceError) -> Self {
+        Error::new(ErrorKind::Unsupported, error)
+    }
+}
+
+/// Checks that ARP requests can be sent from `interface`.
+///
+/// # Returns
+///
+/// A `Result` containing the MAC address of the interface, or an `InterfaceError` for
+/// interfaces without a MAC address and for point-to-point or ARP-less links.
+pub fn check_ethernet_link(interface: &NetworkInterface) -> Result<MacAddr, InterfaceError> {
+    let mac = interface
+        .mac
+        .filter(|mac| *mac != MacAddr::zero())
+        .ok_or_else(|| InterfaceError::NoMacAddress(interface.name.clone()))?;
+    if interface.is_point_to_point() || interface.flags & IFF_NOARP != 0 {
+        return Err(InterfaceError::NotEthernet(interface.name.clone()));
+    }
+    Ok(mac)
+}
+
+/// Decodes the flags of a network interface into their names (`UP`, `BROADCAST`, ...).
+pub fn get_flag_names(interface: &NetworkInterface) -> Vec<&'static str> {
+    const FLAGS: [&str; 8] = [
+        "UP",
+        "BROADCAST",
+        "LOOPBACK",
+        "POINTOPOINT",
+        "MULTICAST",
+        "RUNNING",
+        "DORMANT",
+        "LOWERUP",
+    ];
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+        let rets = [
+        interface.is_up(),
+        interface.is_broadcast(),
+        interface.is_loopback(),
+        interface.is_point_to_point(),
+        interface.is_multicast(),
+        interface.is_running(),
+        interface.is_dormant(),
+        interface.is_lower_up(),
+    ];
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+        let rets = [
+        interface.is_up(),
+        interface.is_broadcast(),
+        interface.is_loopback(),
+        interface.is_point_to_point(),
+        interface.is_multicast(),
+        interface.is_running(),
+        false,
+        false,
+    ];
+    #[cfg(not(unix))]
+        let rets = [
+        interface.is_up(),
+        interface.is_broadcast(),
+        interface.is_loopback(),
+        interface.is_point_to_point(),
+        interface.is_multicast(),
+        false,
+        false,
+        false,
+    ];
+
+    rets.iter()
+        .zip(FLAGS.iter())
+        .filter(|&(ret, _)| ret == &true)
+        .map(|(_, name)| *name)
         .collect()
 }
 
+/// Returns the MTU of the interface called `name`, if it can be read.
+pub fn get_mtu(name: &str) -> Option<u32> {
+    interfaces::Interface::get_by_name(name)
+        .ok()
+        .flatten()
+        .and_then(|interface| interface.get_mtu().ok())
+}
+
+/// Returns the operational state of the interface called `name` (`up`, `down`, `dormant`,
+/// `unknown`, ...) as reported by `/sys/class/net/<name>/operstate`.
+pub fn get_oper_state(name: &str) -> Option<String> {
+    std::fs::read_to_string(format!("/sys/class/net/{}/operstate", name))
+        .ok()
+        .map(|state| state.trim().to_string())
+}
 
-fn get_source_ip_from_interface(interface: &NetworkInterface) -> Result<Ipv4Addr, Error> {
-    let source_ip = interface
+/// Finds the interface designated by `selector` among the available interfaces.
+///
+/// # Parameters
+///
+/// - `interfaces`: The interfaces returned by `get_available_interfaces`.
+/// - `selector`: The name of an interface, or its kernel index (the `index` printed by every
+///   output of the `interfaces` subcommand and expected by `cli::prompt_for_interface`).
+///
+/// # Returns
+///
+/// A `Result` containing the position of the interface in `interfaces`, or an error message
+/// listing the available interfaces.
+///
+/// # Examples
+///
+/// ```
+/// let available_interfaces = get_available_interfaces(&all_interfaces);
+/// let selected = find_interface(&available_interfaces, "eth0")?;
+/// ```
+pub fn find_interface(interfaces: &[&NetworkInterface], selector: &str) -> Result<usize, String> {
+    let by_name = interfaces.iter().position(|interface| interface.name == selector);
+    let by_number = selector
+        .parse::<u32>()
+        .ok()
+        .and_then(|number| interfaces.iter().position(|interface| interface.index == number));
+
+    by_name.or(by_number).ok_or_else(|| {
+        let names: Vec<&str> = interfaces.iter().map(|interface| interface.name.as_str()).collect();
+        format!(
+            "Unknown or unavailable interface: {} (available: {})",
+            selector,
+            if names.is_empty() { String::from("none") } else { names.join(", ") }
+        )
+    })
+}
+
+/// Picks the interface to scan `network` from when none was given.
+///
+/// The interface whose IPv4 subnet contains `network` is used when there is exactly one.
+/// Otherwise the route of the network address in `routes` decides, as long as it points to
+/// one of `interfaces` without going through a gateway (ARP only reaches on-link hosts).
+///
+/// # Parameters
+///
+/// - `interfaces`: The interfaces returned by `get_available_interfaces`.
+/// - `network`: The network to scan.
+/// - `routes`: The kernel routing table, see `route::read_routes`.
+///
+/// # Returns
+///
+/// A `Result` containing the position of the interface in `interfaces`, or an error message
+/// explaining why no interface could be picked.
+pub fn pick_interface(
+    interfaces: &[&NetworkInterface],
+    network: &Ipv4Network,
+    routes: &[Route],
+) -> Result<usize, String> {
+    let on_link: Vec<usize> = interfaces
+        .iter()
+        .enumerate()
+        .filter(|(_, interface)| {
+            interface.ips.iter().any(|ip| match ip {
+                IpNetwork::V4(subnet) => subnet.prefix() <= network.prefix() && subnet.contains(network.network()),
+                IpNetwork::V6(_) => false,
+            })
+        })
+        .map(|(position, _)| position)
+        .collect();
+    if let [position] = on_link[..] {
+        return Ok(position);
+    }
+
+    let routed = route::lookup(routes, network.network())
+        .filter(|route| route.gateway.is_none())
+        .and_then(|route| interfaces.iter().position(|interface| interface.name == route.interface))
+        .filter(|position| on_link.is_empty() || on_link.contains(position));
+    if let Some(position) = routed {
+        return Ok(position);
+    }
+
+    if on_link.is_empty() {
+        Err(format!(
+            "Network {} is not on-link on any available interface, use --interface",
+            network
+        ))
+    } else {
+        let names: Vec<&str> = on_link.iter().map(|position| interfaces[*position].name.as_str()).collect();
+        Err(format!(
+            "Network {} is on-link on several interfaces ({}), use --interface",
+            network,
+            names.join(", ")
+        ))
+    }
+}
+
+/// Chooses the IPv4 address of `interface` the ARP requests are sent from.
+///
+/// An interface with secondary addresses may hold several IPv4 subnets, so the address whose
+/// subnet contains `network` is preferred, then an address inside `network`, then the first
+/// IPv4 address of the interface.
+fn get_source_ip_from_interface(interface: &NetworkInterface, network: &Ipv4Network) -> Result<Ipv4Addr, Error> {
+    let subnets: Vec<Ipv4Network> = interface
         .ips
         .iter()
-        .find(|ip| ip.is_ipv4())
-        .map(|ip| match ip.ip() {
-            IpAddr::V4(ip) => ip,
-            _ => unreachable!(),
-        }).ok_or(std::io::Error::new(std::io::ErrorKind::Other, format!("No IPv4 address found in interface: {}", interface.name)))?;
+        .filter_map(|ip| match ip {
+            IpNetwork::V4(subnet) => Some(*subnet),
+            IpNetwork::V6(_) => None,
+        })
+        .collect();
+
+    let source_ip = subnets
+        .iter()
+        .find(|subnet| subnet.prefix() <= network.prefix() && subnet.contains(network.network()))
+        .or_else(|| subnets.iter().find(|subnet| network.contains(subnet.ip())))
+        .or_else(|| subnets.first())
+        .map(|subnet| subnet.ip())
+        .ok_or(std::io::Error::other(format!("No IPv4 address found in interface: {}", interface.name)))?;
     Ok(source_ip)
 }
 
-pub fn arp_scan(interface: &NetworkInterface, _options: &CliOptions) -> std::result::Result<(), std::io::Error> {
-    let source_ip = get_source_ip_from_interface(interface)?;
-    // dbg!(source_ip);
-    // process::exit(0x0100);
+/// Picks the interface to scan its own subnets from when neither a network nor an interface
+/// was given: the only available interface, else the one of the default route.
+pub fn pick_default_interface(interfaces: &[&NetworkInterface], routes: &[Route]) -> Result<usize, String> {
+    if let [_] = interfaces {
+        return Ok(0);
+    }
 
+    route::lookup(routes, Ipv4Addr::UNSPECIFIED)
+        .and_then(|route| interfaces.iter().position(|interface| interface.name == route.interface))
+        .ok_or_else(|| {
+            let names: Vec<&str> = interfaces.iter().map(|interface| interface.name.as_str()).collect();
+            format!(
+                "No default route among the available interfaces ({}), use --interface or --network",
+                names.join(", ")
+            )
+        })
+}
 
-    let (mut sender, mut receiver) = match pnet::datalink::channel(interface, get_channel_config()) {
-        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
-        Ok(_) => panic!("Unknown channel type"),
-        Err(e) => Err(e)?,
-    };
+/// Returns the IPv4 subnets attached to `interface`, e.g. `192.168.0.0/24` for an
+/// interface with the address `192.168.0.10/24`, each subnet once however many addresses it holds.
+pub fn get_interface_networks(interface: &NetworkInterface) -> Vec<Ipv4Network> {
+    let mut networks: Vec<Ipv4Network> = Vec::new();
+    for ip in &interface.ips {
+        let IpNetwork::V4(subnet) = ip else {
+            continue;
+        };
+        let Ok(network) = Ipv4Network::new(subnet.network(), subnet.prefix()) else {
+            continue;
+        };
+        if !networks.contains(&network) {
+            networks.push(network);
+        }
+    }
+    networks
+}
 
-    sender.send_to(build_arp_packet(interface, source_ip).packet(), None);
+/// Returns the host addresses of a network that should receive an ARP request.
+///
+/// The network and broadcast addresses are skipped for every prefix shorter than /31,
+/// since no host can own them. For /31 (RFC 3021) and /32 networks every address is a host.
+///
+/// # Parameters
+///
+/// - `network`: The `Ipv4Network` passed with `--network`.
+///
+/// # Returns
+///
+/// A vector of target IPv4 addresses in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// use ipnetwork::Ipv4Network;
+/// use your_crate_name::get_target_hosts;
+///
+/// let network: Ipv4Network = "192.168.0.0/30".parse().unwrap();
+/// assert_eq!(get_target_hosts(&network).len(), 2);
+/// ```
+pub fn get_target_hosts(network: &Ipv4Network) -> Vec<Ipv4Addr> {
+    if network.prefix() >= 31 {
+        return network.iter().collect();
+    }
 
-    receive_arp_responses(&mut receiver, interface);
+    network
+        .iter()
+        .filter(|ip| *ip != network.network() && *ip != network.broadcast())
+        .collect()
+}
+
+/// Puts the targets in the order they should be probed for the first time.
+fn order_targets<R: Rng>(mut targets: Vec<Ipv4Addr>, order: TargetOrder, rng: &mut R) -> Vec<Ipv4Addr> {
+    if order == TargetOrder::Random {
+        targets.shuffle(rng);
+    }
+    targets
+}
 
-    Ok(())
+/// Runs an ARP scan of `options.network` through the given transport.
+///
+/// Scans of several interfaces run at the same time each get their own copy of the options
+/// with the network of the interface, see `get_interface_networks`.
+///
+/// ARP requests are sent to every host returned by `get_target_hosts`, at most one every
+/// `options.timing.interval`, and hosts that do not answer are probed again as decided by
+/// `ProbeScheduler`. The `--rate` and `--bandwidth` limits are enforced by a `SendLimiter`.
+/// Every random choice is drawn from `options.seed`, so a scan can be replayed exactly.
+/// Replies are collected into a `ScanReport` until `options.timing.wait` has elapsed since
+/// the last request.
+///
+/// # Parameters
+///
+/// - `transport`: The datalink channel of the interface to send the requests from.
+/// - `options`: The parsed command-line options.
+///
+/// # Returns
+///
+/// A `Result` containing the `ScanReport`, or an `std::io::Error` if the interface has
+/// no IPv4 address or the transport fails to send or receive a frame.
+///
+/// # Examples
+///
+/// ```
+/// let mut transport = PnetTransport::open(&interface)?;
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// println!("{} hosts found", report.hosts.len());
+/// ```
+pub fn arp_scan<T: Transport>(transport: &mut T, options: &CliOptions) -> std::result::Result<ScanReport, std::io::Error> {
+    arp_scan_with_observer(transport, options, &mut |_: &ScanEvent| Ok(()))
 }
 
+/// Runs an ARP scan like `arp_scan`, reporting every event to `observer` as it happens.
+///
+/// An error returned by the observer (e.g. a closed output pipe) stops the scan.
+pub fn arp_scan_with_observer<T: Transport>(
+    transport: &mut T,
+    options: &CliOptions,
+    observer: &mut dyn ScanObserver,
+) -> std::result::Result<ScanReport, std::io::Error> {
+    let interface = transport.interface().clone();
+    let network = options
+        .network
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No network to scan"))?;
+    let source_mac = check_ethernet_link(&interface)?;
+    let source_ip = match options.source_ip {
+        Some(source_ip) => source_ip,
+        None => get_source_ip_from_interface(&interface, &network)?,
+    };
+    let mut report = ScanReport::new(&interface.name, source_ip, Some(source_mac), network, options.profile);
+
+    let timing = &options.timing;
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let targets = order_targets(get_target_hosts(&network), timing.order, &mut rng);
+    report.targets = targets.len() as u64;
+    let mut scheduler = ProbeScheduler::new(targets, timing, &mut rng);
+    let started = Instant::now();
+    let mut limiter = SendLimiter::new(timing.rate, timing.bandwidth, ARP_WIRE_FRAME_LENGTH, started);
+    let mut last_sent = started;
+    let mut next_send = started;
+
+    loop {
+        let now = Instant::now();
+        while now >= next_send && limiter.ready_at(now) <= now {
+            let Some(target_ip) = scheduler.next_probe(now) else {
+                break;
+            };
+            let arp_packet = build_arp_packet(source_mac, source_ip, target_ip);
+            transport.send(arp_packet.packet())?;
+            limiter.consume(now);
+            report.packets_sent += 1;
+
+            let attempt = scheduler.attempts(target_ip);
+            if attempt > 1 {
+                observer.on_event(&ScanEvent::Retry { ip: target_ip, attempt })?;
+            }
+            last_sent = now;
+            next_send = now + timing.next_interval(&mut rng);
+        }
+
+        let scan_deadline = last_sent + timing.wait;
+        if scheduler.is_done() && now >= scan_deadline {
+            break;
+        }
 
-/// Returns the maximum value of a property of a vector of `NetworkInterface` instances.
-/// This function takes a reference to a vector of `NetworkInterface` instances and a closure
-/// that returns a value of type `T` for each `NetworkInterface` instance. It returns the
-/// maximum value of type `T` from all the values returned by the closure.
-fn get_channel_config() -> Config {
-    Config {
-        read_timeout: Some(Duration::from_millis(DATALINK_RCV_TIMEOUT)),
-        ..Config::default()
+        let deadline = match scheduler.next_due(now) {
+            Some(due) => due.max(next_send).max(limiter.ready_at(now)),
+            None => scan_deadline,
+        };
+        receive_arp_responses(
+            transport,
+            &mut report,
+            &mut scheduler,
+            deadline,
+            options.unsolicited,
+            observer,
+        )?;
     }
+
+    report.send_duration = last_sent.duration_since(started);
+    report.unanswered = scheduler.unanswered();
+    report.finished_at = SystemTime::now();
+    observer.on_event(&ScanEvent::Finished(&report))?;
+    Ok(report)
 }
 
-fn receive_arp_responses(receiver: &mut Box<dyn DataLinkReceiver>, interface: &NetworkInterface) {
+
+/// Collects ARP replies into `report` until `deadline` is reached or a target answers.
+///
+/// Only replies addressed to our IP and MAC address are considered. A reply from inside
+/// the scanned network is matched against the outstanding requests in `scheduler`, so
+/// targets that answered are not probed again. Any other reply is only kept when
+/// `unsolicited` is set. Every kept reply is reported to `observer`.
+///
+/// An answer frees a concurrency slot and may end the scan, so the caller has to compute
+/// a new deadline: the function returns as soon as one is matched.
+fn receive_arp_responses<T: Transport>(
+    transport: &mut T,
+    report: &mut ScanReport,
+    scheduler: &mut ProbeScheduler,
+    deadline: Instant,
+    unsolicited: bool,
+    observer: &mut dyn ScanObserver,
+) -> Result<(), Error> {
     loop {
-        match receiver.next() {
-            Ok(packet) => {
-                if let Some(ethernet) = EthernetPacket::new(packet) {
-                    if ethernet.get_ethertype() == pnet::packet::ethernet::EtherTypes::Arp {
-                        if let Some(arp) = ArpPacket::new(ethernet.payload()) {
-                            if arp.get_operation() == ArpOperations::Reply
-                                && arp.get_sender_proto_addr() == interface.ips[0].ip()
-                            {
-                                println!("{:?}", arp);
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Error receiving packet: {:?}", e);
-            }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(());
+        }
+        let Some(frame) = transport.recv(deadline - now)? else {
+            continue;
+        };
+        let Some(arp) = parse_arp_reply(&frame) else {
+            continue;
+        };
+        if arp.get_target_proto_addr() != report.source_ip
+            || Some(arp.get_target_hw_addr()) != report.source_mac
+        {
+            continue;
+        }
+
+        let sender_ip = arp.get_sender_proto_addr();
+        let reply = if report.network.contains(sender_ip) {
+            scheduler.match_reply(sender_ip, Instant::now())
+        } else {
+            ReplyMatch::Unsolicited
+        };
+        if reply == ReplyMatch::Unsolicited && !unsolicited {
+            continue;
+        }
+
+        let host = DiscoveredHost::from_reply(&arp, &report.interface, SystemTime::now(), reply);
+        observer.on_event(&ScanEvent::HostDiscovered(&host))?;
+        report.packets_received += 1;
+        report.hosts.push(host);
+        if matches!(reply, ReplyMatch::Answer(_)) {
+            return Ok(());
         }
     }
 }
 
-fn build_arp_packet(interface: &NetworkInterface, source_ip: Ipv4Addr) -> MutableEthernetPacket {
-    let mut ethernet_packet = MutableEthernetPacket::owned(vec![0u8; 42]).unwrap();
+/// Returns the ARP reply carried by an Ethernet frame, if it is one.
+fn parse_arp_reply(frame: &[u8]) -> Option<ArpPacket<'static>> {
+    let ethernet = EthernetPacket::new(frame)?;
+    if ethernet.get_ethertype() != EtherTypes::Arp {
+        return None;
+    }
+
+    let arp = ArpPacket::owned(ethernet.payload().to_vec())?;
+    (arp.get_operation() == ArpOperations::Reply).then_some(arp)
+}
+
+/// Builds the broadcast ARP request asking for `target_ip`.
+pub(crate) fn build_arp_packet(
+    source_mac: MacAddr,
+    source_ip: Ipv4Addr,
+    target_ip: Ipv4Addr,
+) -> MutableEthernetPacket<'static> {
+    let mut ethernet_packet = MutableEthernetPacket::owned(vec![0u8; ARP_FRAME_LENGTH]).unwrap();
 
     ethernet_packet.set_destination(MacAddr::broadcast());
-    ethernet_packet.set_source(interface.mac.unwrap());
+    ethernet_packet.set_source(source_mac);
     ethernet_packet.set_ethertype(EtherTypes::Arp);
 
     let mut arp_buffer = [0u8; 28];
@@ -131,11 +654,599 @@ fn build_arp_packet(interface: &NetworkInterface, source_ip: Ipv4Addr) -> Mutabl
     arp_packet.set_hw_addr_len(6);
     arp_packet.set_proto_addr_len(4);
     arp_packet.set_operation(ArpOperations::Request);
-    arp_packet.set_sender_hw_addr(interface.mac.unwrap());
+    arp_packet.set_sender_hw_addr(source_mac);
     arp_packet.set_sender_proto_addr(source_ip);
     arp_packet.set_target_hw_addr(MacAddr::zero());
-    arp_packet.set_target_proto_addr(Ipv4Addr::new(0, 0, 0, 0));
+    arp_packet.set_target_proto_addr(target_ip);
 
     ethernet_packet.set_payload(arp_packet.packet_mut());
     ethernet_packet
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::cli::build_command;
+    use crate::transport::{arp_reply, FakeTransport, MockTransport};
+
+    use super::*;
+
+    fn test_interface() -> NetworkInterface {
+        NetworkInterface {
+            name: String::from("eth0"),
+            description: String::new(),
+            index: 2,
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, 0x01)),
+            ips: vec![IpNetwork::V4("192.168.0.10/24".parse().unwrap())],
+            flags: 0,
+        }
+    }
+
+    fn test_options(args: &[&str]) -> CliOptions {
+        let matches = build_command()
+            .try_get_matches_from(
+                ["arp-scan", "--timeout", "5", "--wait", "20", "--interval", "0"]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap();
+        CliOptions::new(&matches).unwrap()
+    }
+
+    #[test]
+    fn test_find_interface_by_name_or_number() {
+        let eth0 = test_interface();
+        let wlan0 = NetworkInterface {
+            name: String::from("wlan0"),
+            index: 5,
+            ..test_interface()
+        };
+        let interfaces = vec![&eth0, &wlan0];
+
+        assert_eq!(find_interface(&interfaces, "wlan0"), Ok(1));
+        // Numbers are kernel indexes, not positions in the list
+        assert_eq!(find_interface(&interfaces, "2"), Ok(0));
+        assert_eq!(find_interface(&interfaces, "5"), Ok(1));
+        assert_eq!(
+            find_interface(&interfaces, "1"),
+            Err(String::from("Unknown or unavailable interface: 1 (available: eth0, wlan0)"))
+        );
+        assert_eq!(
+            find_interface(&[], "eth0"),
+            Err(String::from("Unknown or unavailable interface: eth0 (available: none)"))
+        );
+    }
+
+    #[test]
+    fn test_pick_interface_containing_network() {
+        let eth0 = test_interface();
+        let eth1 = NetworkInterface {
+            name: String::from("eth1"),
+            ips: vec![IpNetwork::V4("10.1.2.3/16".parse().unwrap())],
+            ..test_interface()
+        };
+        let interfaces = vec![&eth0, &eth1];
+
+        assert_eq!(pick_interface(&interfaces, &"10.1.2.0/24".parse().unwrap(), &[]), Ok(1));
+        assert_eq!(pick_interface(&interfaces, &"192.168.0.0/24".parse().unwrap(), &[]), Ok(0));
+        assert_eq!(
+            pick_interface(&interfaces, &"10.0.0.0/8".parse().unwrap(), &[]),
+            Err(String::from("Network 10.0.0.0/8 is not on-link on any available interface, use --interface"))
+        );
+    }
+
+    #[test]
+    fn test_pick_interface_falls_back_to_routes() {
+        let eth0 = test_interface();
+        let wlan0 = NetworkInterface {
+            name: String::from("wlan0"),
+            ..test_interface()
+        };
+        let interfaces = vec![&eth0, &wlan0];
+        let network = "192.168.0.0/24".parse().unwrap();
+        let route = |interface: &str, destination: &str, gateway: Option<Ipv4Addr>| Route {
+            interface: interface.to_string(),
+            destination: destination.parse().unwrap(),
+            gateway,
+            metric: 0,
+        };
+
+        assert_eq!(
+            pick_interface(&interfaces, &network, &[]),
+            Err(String::from("Network 192.168.0.0/24 is on-link on several interfaces (eth0, wlan0), use --interface"))
+        );
+        assert_eq!(
+            pick_interface(&interfaces, &network, &[route("wlan0", "192.168.0.0/24", None)]),
+            Ok(1)
+        );
+        assert_eq!(
+            pick_interface(&interfaces, &"172.16.0.0/24".parse().unwrap(), &[route("eth0", "172.16.0.0/16", None)]),
+            Ok(0)
+        );
+        assert!(pick_interface(
+            &interfaces,
+            &"172.16.0.0/24".parse().unwrap(),
+            &[route("eth0", "0.0.0.0/0", Some(Ipv4Addr::new(192, 168, 0, 1)))]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_source_ip_belongs_to_scanned_subnet() {
+        let interface = NetworkInterface {
+            ips: vec![
+                IpNetwork::V6("fe80::1/64".parse().unwrap()),
+                IpNetwork::V4("192.168.0.10/24".parse().unwrap()),
+                IpNetwork::V4("10.1.2.3/16".parse().unwrap()),
+            ],
+            ..test_interface()
+        };
+        let source_ip = |network: &str| get_source_ip_from_interface(&interface, &network.parse().unwrap()).unwrap();
+
+        assert_eq!(source_ip("10.1.7.0/24"), Ipv4Addr::new(10, 1, 2, 3));
+        assert_eq!(source_ip("10.0.0.0/8"), Ipv4Addr::new(10, 1, 2, 3));
+        assert_eq!(source_ip("172.16.0.0/24"), Ipv4Addr::new(192, 168, 0, 10));
+        assert!(get_source_ip_from_interface(
+            &NetworkInterface { ips: vec![], ..test_interface() },
+            &"10.0.0.0/8".parse().unwrap()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_arp_scan_uses_source_ip_override() {
+        let mut transport = FakeTransport::new(test_interface(), vec![]);
+
+        let report = arp_scan(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--source-ip", "10.0.0.100"]),
+        )
+        .unwrap();
+
+        assert_eq!(report.source_ip, Ipv4Addr::new(10, 0, 0, 100));
+        let ethernet = EthernetPacket::new(&transport.sent[0]).unwrap();
+        let request = ArpPacket::new(ethernet.payload()).unwrap();
+        assert_eq!(request.get_sender_proto_addr(), Ipv4Addr::new(10, 0, 0, 100));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("docker*", "docker0"));
+        assert!(glob_match("veth*", "veth"));
+        assert!(glob_match("*0", "eth0"));
+        assert!(glob_match("e?h*", "eth1"));
+        assert!(glob_match("*a*b*", "xaybzb"));
+        assert!(!glob_match("veth*", "eth0"));
+        assert!(!glob_match("eth?", "eth10"));
+        assert!(!glob_match("", "eth0"));
+    }
+
+    #[test]
+    fn test_filter_interfaces() {
+        let up = |name: &str| NetworkInterface {
+            name: name.to_string(),
+            flags: 0x1043,
+            ..test_interface()
+        };
+        let interfaces = vec![
+            up("eth0"),
+            up("docker0"),
+            up("veth12ab"),
+            NetworkInterface { ips: vec![], ..up("eth1") },
+        ];
+        let names = |filter: &InterfaceFilter| -> Vec<String> {
+            filter_interfaces(&interfaces, filter)
+                .iter()
+                .map(|interface| interface.name.clone())
+                .collect()
+        };
+
+        let exclude = InterfaceFilter {
+            exclude: vec![String::from("docker*"), String::from("veth*")],
+            ..InterfaceFilter::default()
+        };
+        assert_eq!(names(&exclude), vec!["eth0"]);
+
+        let include = InterfaceFilter {
+            include: vec![String::from("eth*")],
+            show_all: true,
+            ..InterfaceFilter::default()
+        };
+        assert_eq!(names(&include), vec!["eth0", "eth1"]);
+        assert_eq!(
+            get_unavailable_reason(&interfaces[3]),
+            Some(String::from("Interface eth1 has no IPv4 address"))
+        );
+    }
+
+    #[test]
+    fn test_pick_default_interface() {
+        let eth0 = test_interface();
+        let wlan0 = NetworkInterface {
+            name: String::from("wlan0"),
+            ..test_interface()
+        };
+        let default_route = Route {
+            interface: String::from("wlan0"),
+            destination: "0.0.0.0/0".parse().unwrap(),
+            gateway: Some(Ipv4Addr::new(192, 168, 0, 1)),
+            metric: 600,
+        };
+
+        assert_eq!(pick_default_interface(&[&eth0], &[]), Ok(0));
+        assert_eq!(pick_default_interface(&[&eth0, &wlan0], &[default_route]), Ok(1));
+        assert_eq!(
+            pick_default_interface(&[&eth0, &wlan0], &[]),
+            Err(String::from(
+                "No default route among the available interfaces (eth0, wlan0), use --interface or --network"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_interface_networks() {
+        let interface = NetworkInterface {
+            ips: vec![
+                IpNetwork::V4("192.168.0.10/24".parse().unwrap()),
+                IpNetwork::V4("192.168.0.11/24".parse().unwrap()),
+                IpNetwork::V6("fe80::1/64".parse().unwrap()),
+                IpNetwork::V4("10.1.2.3/16".parse().unwrap()),
+                IpNetwork::V4("192.168.0.12/24".parse().unwrap()),
+            ],
+            ..test_interface()
+        };
+
+        assert_eq!(
+            get_interface_networks(&interface),
+            vec!["192.168.0.0/24".parse().unwrap(), "10.1.0.0/16".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_check_ethernet_link() {
+        let interface = test_interface();
+        assert_eq!(check_ethernet_link(&interface), Ok(MacAddr::new(0x02, 0, 0, 0, 0, 0x01)));
+
+        let tun = NetworkInterface {
+            name: String::from("tun0"),
+            mac: None,
+            ..test_interface()
+        };
+        assert_eq!(check_ethernet_link(&tun), Err(InterfaceError::NoMacAddress(String::from("tun0"))));
+
+        let wireguard = NetworkInterface {
+            name: String::from("wg0"),
+            mac: Some(MacAddr::zero()),
+            ..test_interface()
+        };
+        assert_eq!(check_ethernet_link(&wireguard), Err(InterfaceError::NoMacAddress(String::from("wg0"))));
+
+        let ppp = NetworkInterface {
+            name: String::from("ppp0"),
+            flags: 0x11 | IFF_NOARP,
+            ..test_interface()
+        };
+        assert_eq!(check_ethernet_link(&ppp), Err(InterfaceError::NotEthernet(String::from("ppp0"))));
+    }
+
+    #[test]
+    fn test_get_available_interfaces_excludes_links_without_arp() {
+        let eth0 = NetworkInterface {
+            flags: 0x1043,
+            ..test_interface()
+        };
+        let tun0 = NetworkInterface {
+            name: String::from("tun0"),
+            mac: None,
+            ..eth0.clone()
+        };
+        let interfaces = vec![eth0, tun0];
+
+        let available: Vec<&str> = get_available_interfaces(&interfaces)
+            .iter()
+            .map(|interface| interface.name.as_str())
+            .collect();
+
+        assert_eq!(available, vec!["eth0"]);
+    }
+
+    #[test]
+    fn test_arp_scan_rejects_interface_without_mac() {
+        let interface = NetworkInterface {
+            mac: None,
+            ..test_interface()
+        };
+        let mut transport = FakeTransport::new(interface, vec![]);
+
+        let result = arp_scan(&mut transport, &test_options(&["--network", "10.0.0.0/30"]));
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::Unsupported);
+        assert!(transport.sent.is_empty());
+    }
+
+    #[test]
+    fn test_arp_scan_collects_replies() {
+        let hosts = vec![
+            (Ipv4Addr::new(192, 168, 0, 20), MacAddr::new(0x02, 0, 0, 0, 0, 0x20)),
+            (Ipv4Addr::new(192, 168, 0, 30), MacAddr::new(0x02, 0, 0, 0, 0, 0x30)),
+        ];
+        let mut transport = FakeTransport::new(test_interface(), hosts.clone());
+
+        let report = arp_scan(&mut transport, &test_options(&["--network", "192.168.0.16/28"])).unwrap();
+
+        let found: Vec<_> = report.hosts.iter().map(|host| (host.ip, host.mac)).collect();
+        assert_eq!(found, hosts);
+        assert_eq!(report.interface, "eth0");
+        assert_eq!(report.source_ip, Ipv4Addr::new(192, 168, 0, 10));
+        assert_eq!(report.packets_received, 2);
+    }
+
+    #[test]
+    fn test_arp_scan_retries_only_silent_hosts() {
+        let hosts = vec![(Ipv4Addr::new(10, 0, 0, 1), MacAddr::new(0x02, 0, 0, 0, 0, 0x02))];
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+
+        let report = arp_scan(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--retry", "3"]),
+        )
+        .unwrap();
+
+        let mut sent = transport.sent_targets();
+        sent.sort();
+        assert_eq!(
+            sent,
+            vec![
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 2),
+                Ipv4Addr::new(10, 0, 0, 2),
+                Ipv4Addr::new(10, 0, 0, 2),
+            ]
+        );
+        assert_eq!(report.packets_sent, 4);
+        assert_eq!(report.hosts.len(), 1);
+    }
+
+    #[test]
+    fn test_arp_scan_ignores_replies_from_outside_the_network() {
+        let mut transport = FakeTransport::new(test_interface(), vec![]);
+        transport.push_frame(arp_reply(
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x99), Ipv4Addr::new(172, 16, 0, 1)),
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x01), Ipv4Addr::new(192, 168, 0, 10)),
+        ));
+
+        let report = arp_scan(&mut transport, &test_options(&["--network", "10.0.0.0/30"])).unwrap();
+
+        assert!(report.hosts.is_empty());
+    }
+
+    #[test]
+    fn test_arp_scan_reports_unsolicited_replies_on_request() {
+        let mut transport = FakeTransport::new(test_interface(), vec![]);
+        transport.push_frame(arp_reply(
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x99), Ipv4Addr::new(172, 16, 0, 1)),
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x01), Ipv4Addr::new(192, 168, 0, 10)),
+        ));
+
+        let report = arp_scan(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--unsolicited"]),
+        )
+        .unwrap();
+
+        assert_eq!(report.hosts.len(), 1);
+        assert!(report.hosts[0].unsolicited);
+        assert_eq!(report.hosts[0].rtt, None);
+    }
+
+    #[test]
+    fn test_arp_scan_ignores_replies_addressed_to_another_host() {
+        let mut transport = FakeTransport::new(test_interface(), vec![]);
+        transport.push_frame(arp_reply(
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x99), Ipv4Addr::new(10, 0, 0, 1)),
+            (MacAddr::new(0x02, 0, 0, 0, 0, 0x77), Ipv4Addr::new(10, 0, 0, 2)),
+        ));
+
+        let report = arp_scan(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--unsolicited"]),
+        )
+        .unwrap();
+
+        assert!(report.hosts.is_empty());
+    }
+
+    #[test]
+    fn test_arp_scan_flags_duplicate_replies() {
+        let ip = Ipv4Addr::new(192, 168, 0, 20);
+        let hosts = vec![
+            (ip, MacAddr::new(0x02, 0, 0, 0, 0, 0x20)),
+            (ip, MacAddr::new(0x02, 0, 0, 0, 0, 0x21)),
+        ];
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+
+        let report = arp_scan(&mut transport, &test_options(&["--network", "192.168.0.20/32"])).unwrap();
+
+        assert_eq!(report.hosts.len(), 2);
+        assert!(!report.hosts[0].duplicate);
+        assert!(report.hosts[0].rtt.is_some());
+        assert!(report.hosts[1].duplicate);
+        assert!(report.hosts[1].rtt.is_some());
+    }
+
+    #[test]
+    fn test_arp_scan_frees_concurrency_slot_on_answer() {
+        let hosts: Vec<_> = (1..=4)
+            .map(|host| (Ipv4Addr::new(10, 0, 0, host), MacAddr::new(0x02, 0, 0, 0, 0, host)))
+            .collect();
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+        let started = Instant::now();
+
+        let matches = build_command()
+            .try_get_matches_from([
+                "arp-scan", "--network", "10.0.0.0/29", "--interval", "0", "--retry", "1",
+                "--concurrency", "1", "--timeout", "500", "--wait", "20",
+            ])
+            .unwrap();
+
+        let report = arp_scan(&mut transport, &CliOptions::new(&matches).unwrap()).unwrap();
+
+        // The 2 silent targets each hold the only slot for the 500 ms timeout, the answered
+        // ones must not
+        assert!(started.elapsed() < Duration::from_millis(1500), "took {:?}", started.elapsed());
+        assert_eq!(report.hosts.len(), 4);
+        let found: Vec<Ipv4Addr> = report.hosts.iter().map(|host| host.ip).collect();
+        assert_eq!(transport.sent_targets()[..4], found[..]);
+    }
+
+    #[test]
+    fn test_arp_scan_waits_from_last_request_once_answered() {
+        let hosts = vec![(Ipv4Addr::new(10, 0, 0, 7), MacAddr::new(0x02, 0, 0, 0, 0, 0x07))];
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+        let started = Instant::now();
+
+        let matches = build_command()
+            .try_get_matches_from(["arp-scan", "--network", "10.0.0.7/32", "--timeout", "2000", "--wait", "100"])
+            .unwrap();
+
+        arp_scan(&mut transport, &CliOptions::new(&matches).unwrap()).unwrap();
+
+        assert!(started.elapsed() < Duration::from_millis(1000), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_arp_scan_with_observer_reports_events() {
+        let hosts = vec![(Ipv4Addr::new(10, 0, 0, 1), MacAddr::new(0x02, 0, 0, 0, 0, 0x02))];
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+        let mut events = Vec::new();
+
+        arp_scan_with_observer(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--retry", "2"]),
+            &mut |event: &ScanEvent| {
+                events.push(match event {
+                    ScanEvent::HostDiscovered(host) => format!("host {}", host.ip),
+                    ScanEvent::Retry { ip, attempt } => format!("retry {} {}", ip, attempt),
+                    ScanEvent::Finished(report) => format!("finished {}", report.hosts.len()),
+                });
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(events, vec!["host 10.0.0.1", "retry 10.0.0.2 2", "finished 1"]);
+    }
+
+    #[test]
+    fn test_arp_scan_stops_on_observer_error() {
+        let hosts = vec![(Ipv4Addr::new(10, 0, 0, 1), MacAddr::new(0x02, 0, 0, 0, 0, 0x02))];
+        let mut transport = FakeTransport::new(test_interface(), hosts);
+
+        let result = arp_scan_with_observer(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30"]),
+            &mut |_: &ScanEvent| Err(Error::from(ErrorKind::BrokenPipe)),
+        );
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_arp_scan_propagates_transport_errors() {
+        let mut transport = MockTransport::new();
+        transport.expect_interface().return_const(test_interface());
+        transport
+            .expect_send()
+            .returning(|_| Err(Error::from(ErrorKind::PermissionDenied)));
+
+        let result = arp_scan(&mut transport, &test_options(&["--network", "10.0.0.0/30"]));
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_arp_scan_treats_receive_timeout_as_idle() {
+        let mut transport = MockTransport::new();
+        transport.expect_interface().return_const(test_interface());
+        transport.expect_send().times(2).returning(|_| Ok(()));
+        transport.expect_recv().returning(|timeout| {
+            std::thread::sleep(timeout.min(Duration::from_millis(1)));
+            Ok(None)
+        });
+
+        let report = arp_scan(
+            &mut transport,
+            &test_options(&["--network", "10.0.0.0/30", "--retry", "1"]),
+        )
+        .unwrap();
+
+        assert_eq!(report.packets_sent, 2);
+        assert!(report.hosts.is_empty());
+    }
+
+    #[test]
+    fn test_get_target_hosts_skips_network_and_broadcast() {
+        let network: Ipv4Network = "192.168.0.0/30".parse().unwrap();
+
+        assert_eq!(
+            get_target_hosts(&network),
+            vec![Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(192, 168, 0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_get_target_hosts_point_to_point_network() {
+        let network: Ipv4Network = "10.0.0.0/31".parse().unwrap();
+
+        assert_eq!(
+            get_target_hosts(&network),
+            vec![Ipv4Addr::new(10, 0, 0, 0), Ipv4Addr::new(10, 0, 0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_get_target_hosts_single_host() {
+        let network: Ipv4Network = "10.0.0.7/32".parse().unwrap();
+
+        assert_eq!(get_target_hosts(&network), vec![Ipv4Addr::new(10, 0, 0, 7)]);
+    }
+
+    #[test]
+    fn test_get_target_hosts_count() {
+        let network: Ipv4Network = "172.16.0.0/24".parse().unwrap();
+
+        assert_eq!(get_target_hosts(&network).len(), 254);
+    }
+
+    #[test]
+    fn test_order_targets_random_is_reproducible_with_seed() {
+        let targets = get_target_hosts(&"10.0.0.0/24".parse().unwrap());
+        let shuffle = |seed| {
+            order_targets(targets.clone(), TargetOrder::Random, &mut StdRng::seed_from_u64(seed))
+        };
+
+        assert_eq!(shuffle(1), shuffle(1));
+        assert_ne!(shuffle(1), targets);
+        assert_eq!(
+            order_targets(targets.clone(), TargetOrder::Sequential, &mut StdRng::seed_from_u64(1)),
+            targets
+        );
+    }
+
+    #[test]
+    fn test_build_arp_packet_targets_host() {
+        let frame = build_arp_packet(
+            MacAddr::new(0x02, 0, 0, 0, 0, 0x01),
+            Ipv4Addr::new(192, 168, 0, 10),
+            Ipv4Addr::new(192, 168, 0, 20),
+        );
+        let arp = ArpPacket::new(frame.payload()).unwrap();
+
+        assert_eq!(frame.get_destination(), MacAddr::broadcast());
+        assert_eq!(arp.get_sender_hw_addr(), MacAddr::new(0x02, 0, 0, 0, 0, 0x01));
+        assert_eq!(arp.get_operation(), ArpOperations::Request);
+        assert_eq!(arp.get_sender_proto_addr(), Ipv4Addr::new(192, 168, 0, 10));
+        assert_eq!(arp.get_target_proto_addr(), Ipv4Addr::new(192, 168, 0, 20));
+    }
+}
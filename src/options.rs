@@ -1,17 +1,28 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use clap::ArgMatches;
 use ipnetwork::Ipv4Network;
+use rand::Rng;
 
-#[derive(Debug)]
+/// Shortest prefix of a network scanned without `--allow-large-network`: a /16 already holds
+/// 65534 targets, each one kept in memory for the whole scan.
+pub const MIN_NETWORK_PREFIX: u8 = 16;
+
+#[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Plain,
     Json,
     Yaml,
     Csv,
+    Ndjson,
+    Xml,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ProfileType {
     Default,
     Fast,
@@ -19,11 +30,309 @@ pub enum ProfileType {
     Chaos,
 }
 
+impl fmt::Display for ProfileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProfileType::Default => "default",
+            ProfileType::Fast => "fast",
+            ProfileType::Stealth => "stealth",
+            ProfileType::Chaos => "chaos",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetOrder {
+    Sequential,
+    Random,
+}
+
+/// The parameters controlling how fast and in which order targets are probed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanTiming {
+    /// Minimal delay between two ARP requests.
+    pub interval: Duration,
+    /// Maximal delay between two ARP requests, each gap is picked at random up to it.
+    pub max_interval: Duration,
+    /// Total number of requests sent to a host that does not answer.
+    pub retry: u32,
+    /// Picks the number of requests of every host at random between 1 and `retry`.
+    pub random_retry: bool,
+    /// Time to wait for a reply before the first retransmission.
+    pub timeout: Duration,
+    /// Factor applied to the timeout after every unanswered request.
+    pub backoff: f64,
+    /// Time to wait for replies after the last request is sent.
+    pub wait: Duration,
+    /// Order in which the targets are probed for the first time.
+    pub order: TargetOrder,
+    /// Maximum number of targets waiting for a reply at the same time.
+    pub concurrency: usize,
+    /// Maximum number of ARP requests per second, enforced on every interface apart.
+    pub rate: Option<u64>,
+    /// Maximum bandwidth used by the ARP requests, in bits per second, enforced on every
+    /// interface apart.
+    pub bandwidth: Option<u64>,
+}
+
+impl ProfileType {
+    /// Returns the preset of scan parameters for this profile.
+    pub fn timing(&self) -> ScanTiming {
+        match self {
+            ProfileType::Default => ScanTiming {
+                interval: Duration::from_millis(1),
+                max_interval: Duration::from_millis(1),
+                retry: 2,
+                random_retry: false,
+                timeout: Duration::from_millis(500),
+                backoff: 1.5,
+                wait: Duration::from_millis(2000),
+                order: TargetOrder::Sequential,
+                concurrency: 256,
+                rate: None,
+                bandwidth: None,
+            },
+            ProfileType::Fast => ScanTiming {
+                interval: Duration::ZERO,
+                max_interval: Duration::ZERO,
+                retry: 1,
+                random_retry: false,
+                timeout: Duration::from_millis(200),
+                backoff: 1.0,
+                wait: Duration::from_millis(500),
+                order: TargetOrder::Sequential,
+                concurrency: 4096,
+                rate: None,
+                bandwidth: None,
+            },
+            ProfileType::Stealth => ScanTiming {
+                interval: Duration::from_millis(100),
+                max_interval: Duration::from_millis(100),
+                retry: 3,
+                random_retry: false,
+                timeout: Duration::from_millis(1000),
+                backoff: 2.0,
+                wait: Duration::from_millis(5000),
+                order: TargetOrder::Random,
+                concurrency: 8,
+                rate: None,
+                bandwidth: None,
+            },
+            ProfileType::Chaos => ScanTiming {
+                interval: Duration::ZERO,
+                max_interval: Duration::from_millis(20),
+                retry: 3,
+                random_retry: true,
+                timeout: Duration::from_millis(500),
+                backoff: 1.5,
+                wait: Duration::from_millis(2000),
+                order: TargetOrder::Random,
+                concurrency: 256,
+                rate: None,
+                bandwidth: None,
+            },
+        }
+    }
+}
+
+impl ScanTiming {
+    /// Returns `true` if the scan depends on the random seed.
+    pub fn is_randomized(&self) -> bool {
+        self.order == TargetOrder::Random || self.max_interval > self.interval || self.random_retry
+    }
+
+    /// Returns the delay to wait before sending the next ARP request.
+    pub fn next_interval<R: Rng>(&self, rng: &mut R) -> Duration {
+        if self.max_interval > self.interval {
+            rng.gen_range(self.interval..=self.max_interval)
+        } else {
+            self.interval
+        }
+    }
+}
+
+/// A column of the CSV output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvColumn {
+    Ip,
+    Mac,
+    Vendor,
+    Rtt,
+    Interface,
+    ReplyTime,
+    Duplicate,
+    Unsolicited,
+    Replies,
+    RttMin,
+    RttAvg,
+    RttMax,
+    RttP95,
+}
+
+impl CsvColumn {
+    pub const NAMES: [&'static str; 13] = [
+        "ip",
+        "mac",
+        "vendor",
+        "rtt",
+        "interface",
+        "reply_time",
+        "duplicate",
+        "unsolicited",
+        "replies",
+        "rtt_min",
+        "rtt_avg",
+        "rtt_max",
+        "rtt_p95",
+    ];
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ip" => Ok(CsvColumn::Ip),
+            "mac" => Ok(CsvColumn::Mac),
+            "vendor" => Ok(CsvColumn::Vendor),
+            "rtt" => Ok(CsvColumn::Rtt),
+            "interface" => Ok(CsvColumn::Interface),
+            "reply_time" => Ok(CsvColumn::ReplyTime),
+            "duplicate" => Ok(CsvColumn::Duplicate),
+            "unsolicited" => Ok(CsvColumn::Unsolicited),
+            "replies" => Ok(CsvColumn::Replies),
+            "rtt_min" => Ok(CsvColumn::RttMin),
+            "rtt_avg" => Ok(CsvColumn::RttAvg),
+            "rtt_max" => Ok(CsvColumn::RttMax),
+            "rtt_p95" => Ok(CsvColumn::RttP95),
+            _ => Err(format!("Unknown CSV column: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for CsvColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CsvColumn::Ip => "ip",
+            CsvColumn::Mac => "mac",
+            CsvColumn::Vendor => "vendor",
+            CsvColumn::Rtt => "rtt",
+            CsvColumn::Interface => "interface",
+            CsvColumn::ReplyTime => "reply_time",
+            CsvColumn::Duplicate => "duplicate",
+            CsvColumn::Unsolicited => "unsolicited",
+            CsvColumn::Replies => "replies",
+            CsvColumn::RttMin => "rtt_min",
+            CsvColumn::RttAvg => "rtt_avg",
+            CsvColumn::RttMax => "rtt_max",
+            CsvColumn::RttP95 => "rtt_p95",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Settings of the CSV output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub columns: Vec<CsvColumn>,
+    pub header: bool,
+    pub delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            columns: vec![CsvColumn::Ip, CsvColumn::Mac, CsvColumn::Vendor, CsvColumn::Rtt],
+            header: true,
+            delimiter: ',',
+        }
+    }
+}
+
+/// Which network interfaces are listed and can be picked for a scan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceFilter {
+    /// Glob patterns of the interface names to keep, every name when empty.
+    pub include: Vec<String>,
+    /// Glob patterns of the interface names to drop.
+    pub exclude: Vec<String>,
+    /// Also lists interfaces that are down or cannot be scanned from.
+    pub show_all: bool,
+    /// Drops the interfaces that are not backed by a device.
+    pub hide_virtual: bool,
+}
+
+/// Options of the `interfaces` subcommand.
 #[derive(Debug)]
+pub struct ListOptions {
+    pub output: OutputFormat,
+    pub csv: CsvOptions,
+    pub interface_filter: InterfaceFilter,
+}
+
+impl ListOptions {
+    pub fn new(matches: &ArgMatches) -> Result<ListOptions, String> {
+        Ok(ListOptions {
+            output: CliOptions::get_output(matches)?,
+            csv: CliOptions::get_csv(matches)?,
+            interface_filter: CliOptions::get_interface_filter(matches),
+        })
+    }
+}
+
+/// An interface given with `--interface`, optionally with the network to scan from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceSelection {
+    /// Name of the interface, or its kernel index.
+    pub selector: String,
+    pub network: Option<Ipv4Network>,
+}
+
+impl FromStr for InterfaceSelection {
+    type Err = String;
+
+    /// Parses `eth0` or `eth0=192.168.0.0/24`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (selector, network) = match value.split_once('=') {
+            Some((selector, network)) => {
+                let network = Ipv4Network::from_str(network)
+                    .map_err(|e| format!("Failed to parse network of interface {}: {}", selector, e))?;
+                (selector, Some(network))
+            }
+            None => (value, None),
+        };
+        if selector.is_empty() {
+            return Err(format!("Missing interface name in: {}", value));
+        }
+
+        Ok(InterfaceSelection {
+            selector: selector.to_string(),
+            network,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct CliOptions {
     pub profile: ProfileType,
     pub output: OutputFormat,
-    pub network: Ipv4Network,
+    /// Network given with `--network`, each interface scans its own subnets without it.
+    pub network: Option<Ipv4Network>,
+    pub timing: ScanTiming,
+    pub seed: u64,
+    pub unsolicited: bool,
+    pub csv: CsvOptions,
+    pub oui_file: Option<PathBuf>,
+    pub pcap: Option<PathBuf>,
+    /// File receiving the scan statistics of the CSV output.
+    pub stats: Option<PathBuf>,
+    pub interfaces: Vec<InterfaceSelection>,
+    pub all_interfaces: bool,
+    /// Scan networks shorter than `MIN_NETWORK_PREFIX`.
+    pub allow_large_network: bool,
+    pub source_ip: Option<Ipv4Addr>,
+    pub interface_filter: InterfaceFilter,
 }
 
 impl CliOptions {
@@ -31,11 +340,42 @@ impl CliOptions {
         let profile = Self::get_profile(matches)?;
         let output = Self::get_output(matches)?;
         let network = Self::get_network(matches)?;
+        let timing = Self::get_timing(matches, &profile)?;
+        let seed = Self::get_seed(matches);
+        let unsolicited = matches.get_flag("unsolicited");
+        let csv = Self::get_csv(matches).and_then(|csv| Self::get_columns(matches, csv))?;
+        let oui_file = matches.get_one::<PathBuf>("oui_file").cloned();
+        let pcap = matches.get_one::<PathBuf>("pcap").cloned();
+        let stats = matches.get_one::<PathBuf>("stats").cloned();
+        if stats.is_some() && !matches!(output, OutputFormat::Csv) {
+            return Err(String::from("--stats is only used by --output csv, the other formats include the statistics"));
+        }
+        let interfaces = Self::get_interfaces(matches)?;
+        let all_interfaces = matches.get_flag("all_interfaces");
+        let allow_large_network = matches.get_flag("allow_large_network");
+        if !allow_large_network {
+            let networks = network.iter().chain(interfaces.iter().filter_map(|s| s.network.as_ref()));
+            Self::check_network_size(networks)?;
+        }
+        let source_ip = matches.get_one::<Ipv4Addr>("source_ip").copied();
+        let interface_filter = Self::get_interface_filter(matches);
 
         Ok(CliOptions {
             profile,
             output,
             network,
+            timing,
+            seed,
+            unsolicited,
+            csv,
+            oui_file,
+            pcap,
+            stats,
+            interfaces,
+            all_interfaces,
+            allow_large_network,
+            source_ip,
+            interface_filter,
         })
     }
 
@@ -67,19 +407,363 @@ impl CliOptions {
             "json" | "j" => OutputFormat::Json,
             "yaml" | "y" => OutputFormat::Yaml,
             "csv" | "c" => OutputFormat::Csv,
-            _ => unreachable!("Expected correct output name {{plain|json|yaml|csv}}")
+            "ndjson" | "n" => OutputFormat::Ndjson,
+            "xml" | "x" => OutputFormat::Xml,
+            _ => unreachable!("Expected correct output name {{plain|json|yaml|csv|ndjson|xml}}")
         };
         Ok(result)
     }
 
     fn get_network(matches: &ArgMatches)
-                   -> Result<Ipv4Network, String>
+                   -> Result<Option<Ipv4Network>, String>
     {
-        let network = matches.get_one::<String>("network")
-            .ok_or("Network not provided")?
-            .as_str();
+        let Some(network) = matches.get_one::<String>("network") else {
+            return Ok(None);
+        };
         let result = Ipv4Network::from_str(network)
             .map_err(|e| format!("Failed to parse IP address: {}", e))?;
-        Ok(result)
+        Ok(Some(result))
+    }
+
+    /// Rejects networks shorter than `MIN_NETWORK_PREFIX`: their targets do not fit in memory.
+    fn check_network_size<'a>(networks: impl Iterator<Item = &'a Ipv4Network>) -> Result<(), String> {
+        match networks.into_iter().find(|network| network.prefix() < MIN_NETWORK_PREFIX) {
+            Some(network) => Err(format!(
+                "Network {} is larger than /{}, pass --allow-large-network to scan it anyway",
+                network, MIN_NETWORK_PREFIX
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn get_interfaces(matches: &ArgMatches) -> Result<Vec<InterfaceSelection>, String> {
+        matches
+            .get_many::<String>("interface")
+            .map(|values| values.map(|value| InterfaceSelection::from_str(value)).collect())
+            .unwrap_or(Ok(Vec::new()))
     }
-}
\ No newline at end of file
+
+    /// Takes the preset of `profile` and overrides every field given on the command line.
+    fn get_timing(matches: &ArgMatches, profile: &ProfileType) -> Result<ScanTiming, String> {
+        let mut timing = profile.timing();
+
+        if let Some(interval) = matches.get_one::<u64>("interval") {
+            timing.interval = Duration::from_millis(*interval);
+            timing.max_interval = timing.max_interval.max(timing.interval);
+        }
+        if let Some(max_interval) = matches.get_one::<u64>("max_interval") {
+            timing.max_interval = Duration::from_millis(*max_interval);
+            if timing.max_interval < timing.interval {
+                return Err(format!(
+                    "Maximal interval must not be lower than the interval of {} ms",
+                    timing.interval.as_millis()
+                ));
+            }
+        }
+        if let Some(retry) = matches.get_one::<u32>("retry") {
+            timing.retry = *retry;
+        }
+        if matches.get_flag("random_retry") {
+            timing.random_retry = true;
+        }
+        if matches.get_flag("no_random_retry") {
+            timing.random_retry = false;
+        }
+        if let Some(timeout) = matches.get_one::<u64>("timeout") {
+            timing.timeout = Duration::from_millis(*timeout);
+        }
+        if let Some(backoff) = matches.get_one::<f64>("backoff") {
+            if !backoff.is_finite() || *backoff < 1.0 {
+                return Err(format!("Backoff factor must be at least 1.0, got {}", backoff));
+            }
+            timing.backoff = *backoff;
+        }
+        if let Some(wait) = matches.get_one::<u64>("wait") {
+            timing.wait = Duration::from_millis(*wait);
+        }
+        if let Some(order) = matches.get_one::<String>("order") {
+            timing.order = match order.as_str() {
+                "sequential" => TargetOrder::Sequential,
+                "random" => TargetOrder::Random,
+                _ => unreachable!("Expected correct target order {{sequential|random}}")
+            };
+        }
+        if let Some(concurrency) = matches.get_one::<u64>("concurrency") {
+            timing.concurrency = usize::try_from(*concurrency).unwrap_or(usize::MAX);
+        }
+        if let Some(rate) = matches.get_one::<u64>("rate") {
+            timing.rate = Some(*rate);
+        }
+        if let Some(bandwidth) = matches.get_one::<u64>("bandwidth") {
+            timing.bandwidth = Some(*bandwidth);
+        }
+
+        Ok(timing)
+    }
+
+    /// Reads the CSV settings shared by scans and the `interfaces` subcommand.
+    fn get_csv(matches: &ArgMatches) -> Result<CsvOptions, String> {
+        let mut csv = CsvOptions {
+            header: !matches.get_flag("no_header"),
+            ..CsvOptions::default()
+        };
+
+        if let Some(delimiter) = matches.get_one::<String>("delimiter") {
+            csv.delimiter = match delimiter.as_str() {
+                "\\t" | "tab" => '\t',
+                _ => {
+                    let mut chars = delimiter.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if !matches!(c, '"' | '\r' | '\n') => c,
+                        _ => return Err(format!("Invalid CSV delimiter: {}", delimiter)),
+                    }
+                }
+            };
+        }
+
+        Ok(csv)
+    }
+
+    /// Applies `--columns`, only given to scans: the interface listing has fixed columns.
+    fn get_columns(matches: &ArgMatches, mut csv: CsvOptions) -> Result<CsvOptions, String> {
+        if let Some(columns) = matches.get_many::<String>("columns") {
+            csv.columns = columns
+                .map(|column| CsvColumn::from_str(column))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(csv)
+    }
+
+    fn get_interface_filter(matches: &ArgMatches) -> InterfaceFilter {
+        let patterns = |id: &str| {
+            matches
+                .get_many::<String>(id)
+                .map(|patterns| patterns.cloned().collect())
+                .unwrap_or_default()
+        };
+
+        InterfaceFilter {
+            include: patterns("include_interface"),
+            exclude: patterns("exclude_interface"),
+            show_all: matches.get_flag("show_all_interfaces"),
+            hide_virtual: matches.get_flag("hide_virtual"),
+        }
+    }
+
+    /// Returns the seed given with `--seed`, or a new random one.
+    fn get_seed(matches: &ArgMatches) -> u64 {
+        matches
+            .get_one::<u64>("seed")
+            .copied()
+            .unwrap_or_else(rand::random)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::build_command;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        let matches = build_command()
+            .try_get_matches_from(["arp-scan", "--network", "192.168.0.0/24"].iter().chain(args))
+            .map_err(|e| e.to_string())?;
+        CliOptions::new(&matches)
+    }
+
+    #[test]
+    fn test_profile_preset_is_used_without_overrides() {
+        let options = parse(&["--profile", "stealth"]).unwrap();
+
+        assert_eq!(options.profile, ProfileType::Stealth);
+        assert_eq!(options.timing, ProfileType::Stealth.timing());
+    }
+
+    #[test]
+    fn test_cli_flags_override_profile_preset() {
+        let options = parse(&["--profile", "fast", "--retry", "4", "--order", "random"]).unwrap();
+
+        assert_eq!(options.timing.retry, 4);
+        assert_eq!(options.timing.order, TargetOrder::Random);
+        assert_eq!(options.timing.timeout, ProfileType::Fast.timing().timeout);
+    }
+
+    #[test]
+    fn test_seed_is_taken_from_cli() {
+        let options = parse(&["--profile", "chaos", "--seed", "42"]).unwrap();
+
+        assert_eq!(options.seed, 42);
+        assert!(options.timing.is_randomized());
+    }
+
+    #[test]
+    fn test_interval_override_raises_max_interval() {
+        let options = parse(&["--profile", "chaos", "--interval", "50"]).unwrap();
+
+        assert_eq!(options.timing.interval, Duration::from_millis(50));
+        assert_eq!(options.timing.max_interval, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_max_interval_below_interval_is_rejected() {
+        let result = parse(&["--interval", "10", "--max-interval", "5"]);
+
+        assert_eq!(
+            result.err().unwrap(),
+            "Maximal interval must not be lower than the interval of 10 ms"
+        );
+    }
+
+    #[test]
+    fn test_rate_and_bandwidth_limits() {
+        let options = parse(&["--rate", "500", "--bandwidth", "1M"]).unwrap();
+
+        assert_eq!(options.timing.rate, Some(500));
+        assert_eq!(options.timing.bandwidth, Some(1_000_000));
+        assert_eq!(ProfileType::Default.timing().rate, None);
+    }
+
+    #[test]
+    fn test_csv_options() {
+        let options = parse(&["--columns", "mac,ip", "--no-header", "--delimiter", "tab"]).unwrap();
+
+        assert_eq!(
+            options.csv,
+            CsvOptions {
+                columns: vec![CsvColumn::Mac, CsvColumn::Ip],
+                header: false,
+                delimiter: '\t',
+            }
+        );
+        assert_eq!(parse(&[]).unwrap().csv, CsvOptions::default());
+    }
+
+    #[test]
+    fn test_stats_file_needs_csv_output() {
+        let options = parse(&["--output", "csv", "--stats", "stats.csv"]).unwrap();
+
+        assert_eq!(options.stats, Some(PathBuf::from("stats.csv")));
+        assert_eq!(
+            parse(&["--stats", "stats.csv"]).err(),
+            Some(String::from("--stats is only used by --output csv, the other formats include the statistics"))
+        );
+    }
+
+    #[test]
+    fn test_csv_delimiter_must_be_one_character() {
+        assert_eq!(
+            parse(&["--delimiter", ";;"]).err().unwrap(),
+            "Invalid CSV delimiter: ;;"
+        );
+        assert!(parse(&["--delimiter", "\""]).is_err());
+    }
+
+    #[test]
+    fn test_interfaces_subcommand_does_not_require_network() {
+        let matches = build_command()
+            .try_get_matches_from(["arp-scan", "interfaces", "--output", "csv", "--delimiter", ";"])
+            .unwrap();
+        let (name, matches) = matches.subcommand().unwrap();
+        let options = ListOptions::new(matches).unwrap();
+
+        assert_eq!(name, "interfaces");
+        assert!(matches!(options.output, OutputFormat::Csv));
+        assert_eq!(options.csv.delimiter, ';');
+        assert!(build_command()
+            .try_get_matches_from(["arp-scan", "interfaces", "--output", "csv", "--columns", "ip"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_interface_filter() {
+        let options = parse(&[
+            "--include-interface",
+            "eth*,wlan*",
+            "--exclude-interface",
+            "eth9",
+            "--hide-virtual",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            options.interface_filter,
+            InterfaceFilter {
+                include: vec![String::from("eth*"), String::from("wlan*")],
+                exclude: vec![String::from("eth9")],
+                show_all: false,
+                hide_virtual: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_interface_selections() {
+        let options = parse(&["--interface", "eth1=10.0.1.0/24,eth2"]).unwrap();
+
+        assert_eq!(
+            options.interfaces,
+            vec![
+                InterfaceSelection {
+                    selector: String::from("eth1"),
+                    network: Some("10.0.1.0/24".parse().unwrap()),
+                },
+                InterfaceSelection {
+                    selector: String::from("eth2"),
+                    network: None,
+                },
+            ]
+        );
+        assert!(InterfaceSelection::from_str("eth1=10.0.1.0/33").is_err());
+        assert!(InterfaceSelection::from_str("=10.0.1.0/24").is_err());
+    }
+
+    #[test]
+    fn test_network_defaults_to_the_local_subnets() {
+        let parse_without_network = |args: &[&str]| {
+            let matches = build_command()
+                .try_get_matches_from(["arp-scan"].iter().chain(args))
+                .map_err(|e| e.to_string())?;
+            CliOptions::new(&matches)
+        };
+
+        assert_eq!(parse_without_network(&["--all-interfaces"]).unwrap().network, None);
+        assert!(parse_without_network(&["--all-interfaces", "--network", "10.0.0.0/24"]).is_err());
+        assert!(parse_without_network(&["--interface", "eth1,eth2"]).is_ok());
+        assert!(parse_without_network(&["--interface", "eth1"]).is_ok());
+        assert!(parse_without_network(&["--interface", "eth1=10.0.1.0/24"]).is_ok());
+        assert_eq!(parse_without_network(&[]).unwrap().network, None);
+    }
+
+    #[test]
+    fn test_large_networks_need_to_be_allowed() {
+        let parse = |args: &[&str]| {
+            let matches = build_command()
+                .try_get_matches_from(["arp-scan"].iter().chain(args))
+                .map_err(|e| e.to_string())?;
+            CliOptions::new(&matches)
+        };
+
+        assert_eq!(
+            parse(&["--network", "10.0.0.0/8"]).err(),
+            Some(String::from("Network 10.0.0.0/8 is larger than /16, pass --allow-large-network to scan it anyway"))
+        );
+        assert!(parse(&["--network", "10.0.0.0/16"]).is_ok());
+        assert!(parse(&["--network", "10.0.0.0/8", "--allow-large-network"]).is_ok());
+        assert!(parse(&["--interface", "eth1=0.0.0.0/0"]).is_err());
+    }
+
+    #[test]
+    fn test_random_retry_of_preset_can_be_turned_off() {
+        assert!(parse(&["--profile", "chaos"]).unwrap().timing.random_retry);
+        assert!(!parse(&["--profile", "chaos", "--no-random-retry"]).unwrap().timing.random_retry);
+        assert!(parse(&["--random-retry", "--no-random-retry"]).is_err());
+    }
+
+    #[test]
+    fn test_backoff_below_one_is_rejected() {
+        let result = parse(&["--backoff", "0.5"]);
+
+        assert_eq!(result.err().unwrap(), "Backoff factor must be at least 1.0, got 0.5");
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use pnet::datalink::NetworkInterface;
+
+use crate::transport::Transport;
+
+/// Magic number of the classic pcap format with microsecond timestamps.
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const PCAP_VERSION_MAJOR: u16 = 2;
+const PCAP_VERSION_MINOR: u16 = 4;
+/// Largest frame stored, longer frames are truncated.
+const PCAP_SNAPLEN: u32 = 65535;
+/// `LINKTYPE_ETHERNET`
+const PCAP_LINKTYPE_ETHERNET: u32 = 1;
+
+/// Writes Ethernet frames in the classic pcap format read by Wireshark and tcpdump.
+///
+/// Fields are written in native byte order, as libpcap does; readers detect it from the
+/// magic number. Cloning a writer over a `SharedWriter` lets scans of several interfaces
+/// record into the same file.
+#[derive(Clone)]
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl PcapWriter<SharedWriter<BufWriter<File>>> {
+    /// Creates (or truncates) the pcap file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(SharedWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+/// A writer shared between threads, each `write_all` landing in one piece.
+pub struct SharedWriter<W: Write> {
+    inner: Arc<Mutex<W>>,
+}
+
+impl<W: Write> SharedWriter<W> {
+    pub fn new(writer: W) -> Self {
+        SharedWriter {
+            inner: Arc::new(Mutex::new(writer)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, W> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<W: Write> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        SharedWriter {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<W: Write> Write for SharedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.lock().write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+        self.lock().write_all(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.lock().flush()
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Writes the pcap global header to `writer`.
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC.to_ne_bytes());
+        header.extend_from_slice(&PCAP_VERSION_MAJOR.to_ne_bytes());
+        header.extend_from_slice(&PCAP_VERSION_MINOR.to_ne_bytes());
+        header.extend_from_slice(&0i32.to_ne_bytes());
+        header.extend_from_slice(&0u32.to_ne_bytes());
+        header.extend_from_slice(&PCAP_SNAPLEN.to_ne_bytes());
+        header.extend_from_slice(&PCAP_LINKTYPE_ETHERNET.to_ne_bytes());
+        writer.write_all(&header)?;
+
+        Ok(PcapWriter { writer })
+    }
+
+    /// Appends one frame captured at `time`.
+    pub fn write_frame(&mut self, frame: &[u8], time: SystemTime) -> Result<(), Error> {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+        let captured = frame.len().min(PCAP_SNAPLEN as usize);
+
+        let mut record = Vec::with_capacity(16 + captured);
+        record.extend_from_slice(&(since_epoch.as_secs() as u32).to_ne_bytes());
+        record.extend_from_slice(&since_epoch.subsec_micros().to_ne_bytes());
+        record.extend_from_slice(&(captured as u32).to_ne_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_ne_bytes());
+        record.extend_from_slice(&frame[..captured]);
+        self.writer.write_all(&record)
+    }
+
+    /// Writes out any buffered frame.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
+/// A `Transport` recording every frame it sends or receives to a pcap file.
+pub struct CaptureTransport<T: Transport, W: Write> {
+    inner: T,
+    pcap: PcapWriter<W>,
+}
+
+impl<T: Transport, W: Write> CaptureTransport<T, W> {
+    pub fn new(inner: T, pcap: PcapWriter<W>) -> Self {
+        CaptureTransport { inner, pcap }
+    }
+
+    /// Flushes the capture and returns the wrapped transport.
+    pub fn finish(mut self) -> Result<T, Error> {
+        self.pcap.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<T: Transport, W: Write> Transport for CaptureTransport<T, W> {
+    fn interface(&self) -> &NetworkInterface {
+        self.inner.interface()
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error> {
+        self.inner.send(frame)?;
+        self.pcap.write_frame(frame, SystemTime::now())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let frame = self.inner.recv(timeout)?;
+        if let Some(frame) = &frame {
+            self.pcap.write_frame(frame, SystemTime::now())?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use pnet::datalink::MacAddr;
+    use pnet::packet::Packet;
+
+    use crate::transport::FakeTransport;
+
+    use super::*;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_write_global_header() {
+        let pcap = PcapWriter::new(Vec::new()).unwrap();
+
+        assert_eq!(pcap.writer.len(), 24);
+        assert_eq!(read_u32(&pcap.writer, 0), PCAP_MAGIC);
+        assert_eq!(read_u32(&pcap.writer, 16), PCAP_SNAPLEN);
+        assert_eq!(read_u32(&pcap.writer, 20), PCAP_LINKTYPE_ETHERNET);
+    }
+
+    #[test]
+    fn test_write_frame_record() {
+        let mut pcap = PcapWriter::new(Vec::new()).unwrap();
+        let time = UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456);
+
+        pcap.write_frame(&[0xff; 42], time).unwrap();
+
+        let record = &pcap.writer[24..];
+        assert_eq!(read_u32(record, 0), 1_700_000_000);
+        assert_eq!(read_u32(record, 4), 123_456);
+        assert_eq!(read_u32(record, 8), 42);
+        assert_eq!(read_u32(record, 12), 42);
+        assert_eq!(&record[16..], &[0xff; 42]);
+    }
+
+    #[test]
+    fn test_capture_transport_records_sent_and_received_frames() {
+        let interface = NetworkInterface {
+            name: String::from("eth0"),
+            description: String::new(),
+            index: 1,
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, 0x01)),
+            ips: vec![],
+            flags: 0,
+        };
+        let host = (Ipv4Addr::new(10, 0, 0, 1), MacAddr::new(0x02, 0, 0, 0, 0, 0x02));
+        let request = crate::net::build_arp_packet(interface.mac.unwrap(), Ipv4Addr::new(10, 0, 0, 10), host.0);
+        let mut transport = CaptureTransport::new(
+            FakeTransport::new(interface, vec![host]),
+            PcapWriter::new(Vec::new()).unwrap(),
+        );
+
+        transport.send(request.packet()).unwrap();
+        transport.recv(Duration::ZERO).unwrap().unwrap();
+        assert_eq!(transport.recv(Duration::ZERO).unwrap(), None);
+
+        assert_eq!(transport.pcap.writer.len(), 24 + 2 * (16 + 42));
+    }
+
+    #[test]
+    fn test_cloned_writers_share_one_file() {
+        let shared = SharedWriter::new(Vec::new());
+        let mut first = PcapWriter::new(shared.clone()).unwrap();
+        let mut second = first.clone();
+
+        first.write_frame(&[0x01; 42], UNIX_EPOCH).unwrap();
+        second.write_frame(&[0x02; 60], UNIX_EPOCH).unwrap();
+
+        assert_eq!(shared.lock().len(), 24 + (16 + 42) + (16 + 60));
+    }
+}
@@ -0,0 +1,398 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::options::ScanTiming;
+
+/// Longest time waited for the reply to one request, however large the backoff grows.
+const MAX_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// How an ARP reply relates to the requests sent so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplyMatch {
+    /// First reply of a probed target, with the time elapsed since its last request.
+    Answer(Duration),
+    /// Another reply of a target that already answered, with the time elapsed since its
+    /// last request.
+    Duplicate(Duration),
+    /// A reply from an address that was never probed.
+    Unsolicited,
+}
+
+/// State of a single scan target.
+#[derive(Debug, Default)]
+struct Target {
+    attempts: u32,
+    retry: u32,
+    answered: bool,
+    last_sent: Option<Instant>,
+    timeout_at: Option<Instant>,
+}
+
+/// Decides which target should be probed next and when.
+///
+/// Every target is sent one request in the order given to `ProbeScheduler::new`. A target
+/// that has not answered is probed again after `timeout`, and every following timeout is
+/// multiplied by `backoff`, until it has been sent `retry` requests in total (a random
+/// number up to `retry` for every target with `random_retry`). Targets that
+/// already answered are never probed again. No more than `concurrency` targets wait for
+/// a reply at the same time.
+#[derive(Debug)]
+pub struct ProbeScheduler {
+    order: Vec<Ipv4Addr>,
+    next_target: usize,
+    targets: HashMap<Ipv4Addr, Target>,
+    timeouts: BinaryHeap<Reverse<(Instant, Ipv4Addr)>>,
+    in_flight: usize,
+    pending: usize,
+    timeout: Duration,
+    backoff: f64,
+    concurrency: usize,
+}
+
+impl ProbeScheduler {
+    /// Creates a scheduler for `targets`.
+    ///
+    /// # Parameters
+    ///
+    /// - `targets`: The addresses to probe, in sending order.
+    /// - `timing`: The retry, timeout, backoff and concurrency settings of the scan.
+    /// - `rng`: The random generator used to pick the retry counts with `random_retry`.
+    pub fn new<R: Rng>(targets: Vec<Ipv4Addr>, timing: &ScanTiming, rng: &mut R) -> Self {
+        let retry = timing.retry.max(1);
+        let states = targets
+            .iter()
+            .map(|ip| {
+                let target = Target {
+                    retry: if timing.random_retry { rng.gen_range(1..=retry) } else { retry },
+                    ..Target::default()
+                };
+                (*ip, target)
+            })
+            .collect();
+
+        ProbeScheduler {
+            order: targets,
+            next_target: 0,
+            targets: states,
+            timeouts: BinaryHeap::new(),
+            in_flight: 0,
+            pending: 0,
+            timeout: timing.timeout,
+            backoff: timing.backoff,
+            concurrency: timing.concurrency.max(1),
+        }
+    }
+
+    /// Returns the next target that should be probed at `now` and records the attempt.
+    ///
+    /// Due retransmissions go before targets that were never probed. Returns `None`
+    /// when nothing has to be sent at `now`.
+    pub fn next_probe(&mut self, now: Instant) -> Option<Ipv4Addr> {
+        if let Some(ip) = self.pop_due_retransmission(now) {
+            self.record_attempt(ip, now);
+            return Some(ip);
+        }
+
+        if self.in_flight >= self.concurrency {
+            return None;
+        }
+        let ip = *self.order.get(self.next_target)?;
+        self.next_target += 1;
+        self.record_attempt(ip, now);
+        Some(ip)
+    }
+
+    /// Returns the instant at which the scheduler state changes next, if it ever does.
+    ///
+    /// This is either now, when a new target can be probed, or the earliest timeout of a
+    /// target waiting for a reply.
+    pub fn next_due(&mut self, now: Instant) -> Option<Instant> {
+        if self.next_target < self.order.len() && self.in_flight < self.concurrency {
+            return Some(now);
+        }
+
+        while let Some(Reverse((due, ip))) = self.timeouts.peek().copied() {
+            if self.targets[&ip].timeout_at == Some(due) {
+                return Some(due);
+            }
+            self.timeouts.pop();
+        }
+        None
+    }
+
+    /// Matches a reply from `ip` received at `now` against the outstanding requests.
+    ///
+    /// A target is marked as answered on its first reply, so it is never probed again. Later
+    /// replies are timed from the same last request.
+    /// A late reply, received after the target was given up, is still an answer.
+    pub fn match_reply(&mut self, ip: Ipv4Addr, now: Instant) -> ReplyMatch {
+        let Some(target) = self.targets.get_mut(&ip) else {
+            return ReplyMatch::Unsolicited;
+        };
+        let Some(last_sent) = target.last_sent else {
+            return ReplyMatch::Unsolicited;
+        };
+        if target.answered {
+            return ReplyMatch::Duplicate(now.saturating_duration_since(last_sent));
+        }
+
+        target.answered = true;
+        if target.timeout_at.take().is_some() {
+            self.in_flight -= 1;
+            if target.attempts < target.retry {
+                self.pending -= 1;
+            }
+        }
+        ReplyMatch::Answer(now.saturating_duration_since(last_sent))
+    }
+
+    /// Returns the number of requests sent to `ip` so far.
+    pub fn attempts(&self, ip: Ipv4Addr) -> u32 {
+        self.targets.get(&ip).map_or(0, |target| target.attempts)
+    }
+
+    /// Returns the targets that never answered, in probing order.
+    pub fn unanswered(&self) -> Vec<Ipv4Addr> {
+        self.order
+            .iter()
+            .filter(|ip| !self.targets[*ip].answered)
+            .copied()
+            .collect()
+    }
+
+    /// Returns `true` once every target has been answered or has used all its attempts.
+    pub fn is_done(&self) -> bool {
+        self.next_target >= self.order.len() && self.pending == 0
+    }
+
+    /// Pops the targets whose timeout expired at `now`.
+    ///
+    /// Targets that used all their attempts are given up, freeing a concurrency slot.
+    /// The first target that can still be retransmitted is returned.
+    fn pop_due_retransmission(&mut self, now: Instant) -> Option<Ipv4Addr> {
+        while let Some(Reverse((due, ip))) = self.timeouts.peek().copied() {
+            let target = self.targets.get_mut(&ip)?;
+            if target.timeout_at != Some(due) {
+                self.timeouts.pop();
+                continue;
+            }
+            if due > now {
+                return None;
+            }
+
+            self.timeouts.pop();
+            target.timeout_at = None;
+            self.in_flight -= 1;
+            if target.attempts < target.retry {
+                self.pending -= 1;
+                return Some(ip);
+            }
+        }
+        None
+    }
+
+    fn record_attempt(&mut self, ip: Ipv4Addr, now: Instant) {
+        let target = self.targets.entry(ip).or_default();
+        target.attempts += 1;
+        target.last_sent = Some(now);
+
+        // A large backoff overflows `Duration`, so the timeout is clamped instead
+        let factor = self.backoff.powi(target.attempts as i32 - 1);
+        let timeout = Duration::try_from_secs_f64(self.timeout.as_secs_f64() * factor)
+            .map_or(MAX_TIMEOUT, |timeout| timeout.min(MAX_TIMEOUT));
+        let timeout_at = now.checked_add(timeout).unwrap_or(now);
+        target.timeout_at = Some(timeout_at);
+        self.timeouts.push(Reverse((timeout_at, ip)));
+        self.in_flight += 1;
+        if target.attempts < target.retry {
+            self.pending += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::options::ProfileType;
+
+    use super::*;
+
+    fn targets(count: u8) -> Vec<Ipv4Addr> {
+        (1..=count).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect()
+    }
+
+    fn timing(retry: u32, backoff: f64, concurrency: usize) -> ScanTiming {
+        ScanTiming {
+            retry,
+            timeout: Duration::from_millis(100),
+            backoff,
+            concurrency,
+            ..ProfileType::Default.timing()
+        }
+    }
+
+    #[test]
+    fn test_next_probe_sends_every_target_once() {
+        let now = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(3), &timing(1, 1.0, 16), &mut StdRng::seed_from_u64(0));
+
+        let sent: Vec<_> = std::iter::from_fn(|| scheduler.next_probe(now)).collect();
+
+        assert_eq!(sent, targets(3));
+        assert!(scheduler.is_done());
+        assert_eq!(scheduler.next_probe(now + Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_next_probe_retransmits_with_backoff() {
+        let start = Instant::now();
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let mut scheduler = ProbeScheduler::new(vec![ip], &timing(3, 2.0, 16), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(scheduler.next_probe(start), Some(ip));
+        assert_eq!(scheduler.next_probe(start + Duration::from_millis(99)), None);
+        assert_eq!(scheduler.next_due(start), Some(start + Duration::from_millis(100)));
+
+        let second = start + Duration::from_millis(100);
+        assert_eq!(scheduler.next_probe(second), Some(ip));
+        assert_eq!(scheduler.next_due(second), Some(second + Duration::from_millis(200)));
+        assert_eq!(scheduler.next_probe(second + Duration::from_millis(199)), None);
+
+        let third = second + Duration::from_millis(200);
+        assert_eq!(scheduler.next_probe(third), Some(ip));
+        assert!(scheduler.is_done());
+        assert_eq!(scheduler.next_probe(third + Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_huge_backoff_is_clamped() {
+        let start = Instant::now();
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let mut scheduler = ProbeScheduler::new(vec![ip], &timing(3, 1e30, 16), &mut StdRng::seed_from_u64(0));
+
+        scheduler.next_probe(start);
+        let second = start + Duration::from_millis(100);
+        assert_eq!(scheduler.next_probe(second), Some(ip));
+        assert_eq!(scheduler.next_due(second), Some(second + MAX_TIMEOUT));
+    }
+
+    #[test]
+    fn test_answered_target_is_not_probed_again() {
+        let start = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(2), &timing(3, 1.0, 16), &mut StdRng::seed_from_u64(0));
+        scheduler.next_probe(start);
+        scheduler.next_probe(start);
+
+        let reply_at = start + Duration::from_millis(3);
+        assert_eq!(
+            scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 1), reply_at),
+            ReplyMatch::Answer(Duration::from_millis(3))
+        );
+        assert_eq!(
+            scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 1), reply_at + Duration::from_millis(2)),
+            ReplyMatch::Duplicate(Duration::from_millis(5))
+        );
+
+        let later = start + Duration::from_millis(100);
+        assert_eq!(scheduler.next_probe(later), Some(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!(scheduler.next_probe(later), None);
+
+        assert_eq!(
+            scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 2), later),
+            ReplyMatch::Answer(Duration::ZERO)
+        );
+        assert!(scheduler.is_done());
+    }
+
+    #[test]
+    fn test_unanswered_targets_keep_probing_order() {
+        let now = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(3), &timing(1, 1.0, 16), &mut StdRng::seed_from_u64(0));
+        while scheduler.next_probe(now).is_some() {}
+
+        scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 2), now);
+
+        assert_eq!(
+            scheduler.unanswered(),
+            vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn test_match_reply_unknown_and_unprobed_targets_are_unsolicited() {
+        let now = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(2), &timing(2, 1.0, 16), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(
+            scheduler.match_reply(Ipv4Addr::new(192, 168, 0, 1), now),
+            ReplyMatch::Unsolicited
+        );
+        assert_eq!(
+            scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 1), now),
+            ReplyMatch::Unsolicited
+        );
+    }
+
+    #[test]
+    fn test_match_reply_after_target_was_given_up() {
+        let start = Instant::now();
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let mut scheduler = ProbeScheduler::new(vec![ip], &timing(1, 1.0, 16), &mut StdRng::seed_from_u64(0));
+        scheduler.next_probe(start);
+        let late = start + Duration::from_secs(1);
+        scheduler.next_probe(late);
+
+        assert_eq!(scheduler.match_reply(ip, late), ReplyMatch::Answer(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_concurrency_limits_targets_waiting_for_reply() {
+        let start = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(3), &timing(1, 1.0, 2), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(scheduler.next_probe(start), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(scheduler.next_probe(start), Some(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!(scheduler.next_probe(start), None);
+        assert_eq!(scheduler.next_due(start), Some(start + Duration::from_millis(100)));
+
+        scheduler.match_reply(Ipv4Addr::new(10, 0, 0, 1), start);
+        assert_eq!(scheduler.next_probe(start), Some(Ipv4Addr::new(10, 0, 0, 3)));
+        assert!(scheduler.is_done());
+    }
+
+    #[test]
+    fn test_concurrency_slot_is_freed_when_target_is_given_up() {
+        let start = Instant::now();
+        let mut scheduler = ProbeScheduler::new(targets(2), &timing(1, 1.0, 1), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(scheduler.next_probe(start), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(scheduler.next_probe(start), None);
+
+        let expired = start + Duration::from_millis(100);
+        assert_eq!(scheduler.next_probe(expired), Some(Ipv4Addr::new(10, 0, 0, 2)));
+    }
+
+    #[test]
+    fn test_random_retry_is_reproducible_with_seed() {
+        let timing = ScanTiming {
+            random_retry: true,
+            ..timing(5, 1.0, 256)
+        };
+        let retries = |seed| {
+            let scheduler = ProbeScheduler::new(targets(50), &timing, &mut StdRng::seed_from_u64(seed));
+            let mut retries: Vec<_> = scheduler.targets.iter().map(|(ip, t)| (*ip, t.retry)).collect();
+            retries.sort();
+            retries
+        };
+
+        assert_eq!(retries(7), retries(7));
+        assert!(retries(7).iter().all(|(_, retry)| (1..=5).contains(retry)));
+        assert!(retries(7).iter().any(|(_, retry)| *retry != 5));
+    }
+}
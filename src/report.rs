@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::io::Error;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime};
+
+use ipnetwork::Ipv4Network;
+use pnet::datalink::MacAddr;
+use pnet::packet::arp::ArpPacket;
+
+use crate::options::ProfileType;
+use crate::probe::ReplyMatch;
+
+/// Raw fields of an ARP packet exactly as they were received on the wire.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArpFields {
+    pub hardware_type: u16,
+    pub protocol_type: u16,
+    pub hw_addr_len: u8,
+    pub proto_addr_len: u8,
+    pub operation: u16,
+    pub sender_hw_addr: MacAddr,
+    pub sender_proto_addr: Ipv4Addr,
+    pub target_hw_addr: MacAddr,
+    pub target_proto_addr: Ipv4Addr,
+}
+
+impl From<&ArpPacket<'_>> for ArpFields {
+    fn from(arp: &ArpPacket) -> Self {
+        ArpFields {
+            hardware_type: arp.get_hardware_type().0,
+            protocol_type: arp.get_protocol_type().0,
+            hw_addr_len: arp.get_hw_addr_len(),
+            proto_addr_len: arp.get_proto_addr_len(),
+            operation: arp.get_operation().0,
+            sender_hw_addr: arp.get_sender_hw_addr(),
+            sender_proto_addr: arp.get_sender_proto_addr(),
+            target_hw_addr: arp.get_target_hw_addr(),
+            target_proto_addr: arp.get_target_proto_addr(),
+        }
+    }
+}
+
+/// A host that answered one of our ARP requests.
+///
+/// Every reply produces its own `DiscoveredHost`, so a host answering twice
+/// (or two hosts claiming the same IP) appears twice in the report, the second
+/// time flagged as `duplicate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredHost {
+    pub ip: Ipv4Addr,
+    pub mac: MacAddr,
+    pub interface: String,
+    pub reply_time: SystemTime,
+    /// Vendor of the network card, looked up from the OUI of the MAC address.
+    pub vendor: Option<String>,
+    /// Time between the last request sent to the host and its reply.
+    pub rtt: Option<Duration>,
+    /// The host already answered before.
+    pub duplicate: bool,
+    /// The reply does not match any request of the scan.
+    pub unsolicited: bool,
+    pub arp: ArpFields,
+}
+
+impl DiscoveredHost {
+    /// Builds a `DiscoveredHost` from an ARP reply received on `interface`.
+    pub fn from_reply(arp: &ArpPacket, interface: &str, reply_time: SystemTime, reply: ReplyMatch) -> Self {
+        DiscoveredHost {
+            ip: arp.get_sender_proto_addr(),
+            mac: arp.get_sender_hw_addr(),
+            interface: interface.to_string(),
+            reply_time,
+            vendor: None,
+            rtt: match reply {
+                ReplyMatch::Answer(rtt) | ReplyMatch::Duplicate(rtt) => Some(rtt),
+                ReplyMatch::Unsolicited => None,
+            },
+            duplicate: matches!(reply, ReplyMatch::Duplicate(_)),
+            unsolicited: reply == ReplyMatch::Unsolicited,
+            arp: ArpFields::from(arp),
+        }
+    }
+}
+
+/// An interface and the network scanned from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanSegment {
+    pub interface: String,
+    pub source_ip: Ipv4Addr,
+    pub source_mac: Option<MacAddr>,
+    pub network: Ipv4Network,
+}
+
+/// The result of a single ARP scan, returned by `net::arp_scan`.
+///
+/// Reports of scans run at the same time on several interfaces are combined with
+/// `ScanReport::merge`: `segments` then lists every scanned interface and network, while
+/// `interface`, `source_ip`, `source_mac` and `network` describe the first one.
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    pub interface: String,
+    pub source_ip: Ipv4Addr,
+    pub source_mac: Option<MacAddr>,
+    pub network: Ipv4Network,
+    pub profile: ProfileType,
+    pub started_at: SystemTime,
+    pub finished_at: SystemTime,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    /// Number of addresses probed.
+    pub targets: u64,
+    /// Probed addresses that never answered, in probing order.
+    pub unanswered: Vec<Ipv4Addr>,
+    /// Time between the start of the scan and the last ARP request.
+    pub send_duration: Duration,
+    pub hosts: Vec<DiscoveredHost>,
+    pub segments: Vec<ScanSegment>,
+}
+
+impl ScanReport {
+    /// Creates an empty report for a scan starting now.
+    pub fn new(
+        interface: &str,
+        source_ip: Ipv4Addr,
+        source_mac: Option<MacAddr>,
+        network: Ipv4Network,
+        profile: ProfileType,
+    ) -> Self {
+        let now = SystemTime::now();
+        let segment = ScanSegment {
+            interface: interface.to_string(),
+            source_ip,
+            source_mac,
+            network,
+        };
+        ScanReport {
+            interface: interface.to_string(),
+            source_ip,
+            source_mac,
+            network,
+            profile,
+            started_at: now,
+            finished_at: now,
+            packets_sent: 0,
+            packets_received: 0,
+            targets: 0,
+            unanswered: Vec::new(),
+            send_duration: Duration::ZERO,
+            hosts: Vec::new(),
+            segments: vec![segment],
+        }
+    }
+
+    /// Combines the reports of scans run at the same time into one report.
+    ///
+    /// Counters are summed, the scan spans from the earliest start to the latest end, and
+    /// hosts are sorted by reply time, each one keeping the interface it was seen on.
+    /// Interfaces send at the same time but the networks of one interface one after the
+    /// other, so the send durations are added up per interface before keeping the longest.
+    /// Returns `None` when `reports` is empty.
+    pub fn merge(reports: Vec<ScanReport>) -> Option<ScanReport> {
+        let mut send_durations = BTreeMap::<String, Duration>::new();
+        for report in &reports {
+            *send_durations.entry(report.interface.clone()).or_default() += report.send_duration;
+        }
+
+        let mut reports = reports.into_iter();
+        let mut merged = reports.next()?;
+        merged.send_duration = send_durations.into_values().max().unwrap_or_default();
+        for report in reports {
+            merged.started_at = merged.started_at.min(report.started_at);
+            merged.finished_at = merged.finished_at.max(report.finished_at);
+            merged.packets_sent += report.packets_sent;
+            merged.packets_received += report.packets_received;
+            merged.targets += report.targets;
+            merged.unanswered.extend(report.unanswered);
+            merged.hosts.extend(report.hosts);
+            merged.segments.extend(report.segments);
+        }
+        merged.hosts.sort_by_key(|host| host.reply_time);
+        Some(merged)
+    }
+
+    /// Returns the achieved send rate in packets per second.
+    ///
+    /// `send_duration` ends with the last request, so it covers one interval less than
+    /// the number of packets sent. Returns 0 for scans of fewer than 2 packets.
+    pub fn send_rate(&self) -> f64 {
+        if self.packets_sent < 2 || self.send_duration.is_zero() {
+            return 0.0;
+        }
+        (self.packets_sent - 1) as f64 / self.send_duration.as_secs_f64()
+    }
+}
+
+/// Something that happened during a running scan.
+#[derive(Debug)]
+pub enum ScanEvent<'a> {
+    /// A reply was received and kept in the report.
+    HostDiscovered(&'a DiscoveredHost),
+    /// A request was sent again to a host that did not answer.
+    Retry { ip: Ipv4Addr, attempt: u32 },
+    /// The scan is over, the report is complete.
+    Finished(&'a ScanReport),
+}
+
+/// Receives the events of a running scan, see `net::arp_scan_with_observer`.
+pub trait ScanObserver {
+    fn on_event(&mut self, event: &ScanEvent) -> Result<(), Error>;
+}
+
+impl<F: FnMut(&ScanEvent) -> Result<(), Error>> ScanObserver for F {
+    fn on_event(&mut self, event: &ScanEvent) -> Result<(), Error> {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+pub mod fixtures {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    /// Returns a finished report with two hosts, the second one a duplicate reply.
+    pub fn sample_report() -> ScanReport {
+        let started_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let source_mac = MacAddr::new(0x02, 0, 0, 0, 0, 0x01);
+        let host = |ip: Ipv4Addr, mac: MacAddr, rtt_us: Option<u64>, duplicate: bool| DiscoveredHost {
+            ip,
+            mac,
+            interface: String::from("eth0"),
+            reply_time: started_at + Duration::from_millis(10),
+            vendor: None,
+            rtt: rtt_us.map(Duration::from_micros),
+            duplicate,
+            unsolicited: false,
+            arp: ArpFields {
+                hardware_type: 1,
+                protocol_type: 0x0800,
+                hw_addr_len: 6,
+                proto_addr_len: 4,
+                operation: 2,
+                sender_hw_addr: mac,
+                sender_proto_addr: ip,
+                target_hw_addr: source_mac,
+                target_proto_addr: Ipv4Addr::new(192, 168, 0, 10),
+            },
+        };
+
+        let network: Ipv4Network = "192.168.0.0/24".parse().unwrap();
+        ScanReport {
+            interface: String::from("eth0"),
+            source_ip: Ipv4Addr::new(192, 168, 0, 10),
+            source_mac: Some(source_mac),
+            network,
+            profile: ProfileType::Default,
+            started_at,
+            finished_at: started_at + Duration::from_millis(2500),
+            packets_sent: 254,
+            packets_received: 2,
+            targets: 254,
+            unanswered: (2..=254).map(|host| Ipv4Addr::new(192, 168, 0, host)).collect(),
+            send_duration: Duration::from_millis(500),
+            hosts: vec![
+                host(Ipv4Addr::new(192, 168, 0, 1), MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55), Some(1500), false),
+                host(Ipv4Addr::new(192, 168, 0, 1), MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x66), None, true),
+            ],
+            segments: vec![ScanSegment {
+                interface: String::from("eth0"),
+                source_ip: Ipv4Addr::new(192, 168, 0, 10),
+                source_mac: Some(source_mac),
+                network,
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_reports_of_several_interfaces() {
+        let first = fixtures::sample_report();
+        let mut second = fixtures::sample_report();
+        second.interface = String::from("eth1");
+        second.segments[0].interface = String::from("eth1");
+        second.started_at -= Duration::from_secs(1);
+        second.packets_sent = 10;
+        second.hosts.truncate(1);
+        second.hosts[0].interface = String::from("eth1");
+        second.hosts[0].reply_time -= Duration::from_secs(1);
+
+        let merged = ScanReport::merge(vec![first.clone(), second.clone()]).unwrap();
+
+        assert_eq!(merged.interface, "eth0");
+        assert_eq!(merged.started_at, second.started_at);
+        assert_eq!(merged.finished_at, first.finished_at);
+        assert_eq!(merged.packets_sent, 264);
+        assert_eq!(merged.targets, 508);
+        assert_eq!(merged.segments.len(), 2);
+        let interfaces: Vec<&str> = merged.hosts.iter().map(|host| host.interface.as_str()).collect();
+        assert_eq!(interfaces, vec!["eth1", "eth0", "eth0"]);
+        assert!(ScanReport::merge(vec![]).is_none());
+    }
+
+    #[test]
+    fn test_merge_adds_up_send_durations_of_one_interface() {
+        let first = fixtures::sample_report();
+        let mut second = fixtures::sample_report();
+        second.network = "192.168.1.0/24".parse().unwrap();
+        let mut other = fixtures::sample_report();
+        other.interface = String::from("eth1");
+
+        let merged = ScanReport::merge(vec![first, second, other]).unwrap();
+
+        assert_eq!(merged.send_duration, Duration::from_millis(1000));
+        assert_eq!(merged.send_rate(), 761.0);
+    }
+
+    #[test]
+    fn test_send_rate_of_a_single_packet_is_zero() {
+        let mut report = fixtures::sample_report();
+        report.packets_sent = 1;
+        report.send_duration = Duration::from_nanos(1);
+
+        assert_eq!(report.send_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_send_rate_counts_intervals_between_packets() {
+        // --rate 10: 6 packets sent 100 ms apart
+        let mut report = fixtures::sample_report();
+        report.packets_sent = 6;
+        report.send_duration = Duration::from_millis(500);
+
+        assert_eq!(report.send_rate(), 10.0);
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io::Error;
+use std::net::Ipv4Addr;
+
+use ipnetwork::Ipv4Network;
+
+/// The kernel IPv4 routing table.
+pub const PROC_NET_ROUTE: &str = "/proc/net/route";
+
+/// `RTF_GATEWAY`: the destination is reached through a gateway.
+const RTF_GATEWAY: u16 = 0x0002;
+
+/// One entry of the kernel IPv4 routing table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub interface: String,
+    pub destination: Ipv4Network,
+    pub gateway: Option<Ipv4Addr>,
+    pub metric: u32,
+}
+
+/// Parses the content of `/proc/net/route`.
+///
+/// Addresses are written by the kernel as hexadecimal numbers in host byte order. The
+/// header line and malformed lines are skipped.
+pub fn parse_routes(content: &str) -> Vec<Route> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            let destination = parse_address(fields[1])?;
+            let gateway = parse_address(fields[2])?;
+            let flags = u16::from_str_radix(fields[3], 16).ok()?;
+            let metric = fields[6].parse::<u32>().ok()?;
+            let mask = parse_address(fields[7])?;
+
+            Some(Route {
+                interface: fields[0].to_string(),
+                destination: Ipv4Network::with_netmask(destination, mask).ok()?,
+                gateway: (flags & RTF_GATEWAY != 0).then_some(gateway),
+                metric,
+            })
+        })
+        .collect()
+}
+
+/// Reads the routing table from `PROC_NET_ROUTE`.
+pub fn read_routes() -> Result<Vec<Route>, Error> {
+    Ok(parse_routes(&fs::read_to_string(PROC_NET_ROUTE)?))
+}
+
+/// Returns the route the kernel would use to reach `ip`: the longest matching prefix,
+/// then the lowest metric.
+pub fn lookup(routes: &[Route], ip: Ipv4Addr) -> Option<&Route> {
+    routes
+        .iter()
+        .filter(|route| route.destination.contains(ip))
+        .min_by_key(|route| (std::cmp::Reverse(route.destination.prefix()), route.metric))
+}
+
+fn parse_address(hex: &str) -> Option<Ipv4Addr> {
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .map(|value| Ipv4Addr::from(value.to_ne_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0100A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0000A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+broken line
+";
+
+    #[test]
+    fn test_parse_routes() {
+        let routes = parse_routes(ROUTES);
+
+        assert_eq!(routes.len(), 3);
+        assert_eq!(
+            routes[0],
+            Route {
+                interface: String::from("eth0"),
+                destination: "0.0.0.0/0".parse().unwrap(),
+                gateway: Some(Ipv4Addr::new(192, 168, 0, 1)),
+                metric: 100,
+            }
+        );
+        assert_eq!(routes[1].destination, "192.168.0.0/24".parse().unwrap());
+        assert_eq!(routes[1].gateway, None);
+    }
+
+    #[test]
+    fn test_lookup_prefers_longest_prefix_then_metric() {
+        let routes = parse_routes(ROUTES);
+
+        let on_link = lookup(&routes, Ipv4Addr::new(192, 168, 0, 20)).unwrap();
+        assert_eq!(on_link.interface, "eth0");
+        assert_eq!(on_link.gateway, None);
+
+        let routed = lookup(&routes, Ipv4Addr::new(10, 0, 0, 1)).unwrap();
+        assert_eq!(routed.gateway, Some(Ipv4Addr::new(192, 168, 0, 1)));
+        assert_eq!(lookup(&[], Ipv4Addr::new(10, 0, 0, 1)), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crate::report::ScanReport;
+
+/// Distribution of round-trip times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttSummary {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+    /// 95th percentile, using the nearest-rank method.
+    pub p95: Duration,
+}
+
+impl RttSummary {
+    /// Summarizes `samples`, or returns `None` when there is none.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+
+        Some(RttSummary {
+            min: sorted[0],
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            max: sorted[sorted.len() - 1],
+            p95: sorted[rank.max(1) - 1],
+        })
+    }
+}
+
+/// Replies and round-trip times of a single answering address on one interface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostStatistics {
+    pub interface: String,
+    pub ip: Ipv4Addr,
+    /// Every reply received from the address, duplicates included.
+    pub replies: u64,
+    pub rtt: Option<RttSummary>,
+}
+
+/// How healthy a finished scan was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanStatistics {
+    pub packets_sent: u64,
+    pub replies: u64,
+    pub duplicates: u64,
+    pub unsolicited: u64,
+    pub targets: u64,
+    /// Number of probed addresses that never answered.
+    pub unanswered: u64,
+    /// Distribution of the round-trip times of every reply, duplicates included.
+    pub rtt: Option<RttSummary>,
+    /// One entry per answering address and interface it answered on, sorted by interface
+    /// then address.
+    pub hosts: Vec<HostStatistics>,
+    /// Achieved send rate in packets per second.
+    pub send_rate: f64,
+    /// Wall-clock time between the start and the end of the scan.
+    pub duration: Duration,
+}
+
+impl From<&ScanReport> for ScanStatistics {
+    fn from(report: &ScanReport) -> Self {
+        let mut samples = BTreeMap::<(&str, Ipv4Addr), (u64, Vec<Duration>)>::new();
+        for host in &report.hosts {
+            let (replies, rtts) = samples.entry((host.interface.as_str(), host.ip)).or_default();
+            *replies += 1;
+            rtts.extend(host.rtt);
+        }
+        let all_rtts: Vec<Duration> = report.hosts.iter().filter_map(|host| host.rtt).collect();
+
+        ScanStatistics {
+            packets_sent: report.packets_sent,
+            replies: report.packets_received,
+            duplicates: report.hosts.iter().filter(|host| host.duplicate).count() as u64,
+            unsolicited: report.hosts.iter().filter(|host| host.unsolicited).count() as u64,
+            targets: report.targets,
+            unanswered: report.unanswered.len() as u64,
+            rtt: RttSummary::from_samples(&all_rtts),
+            hosts: samples
+                .into_iter()
+                .map(|((interface, ip), (replies, rtts))| HostStatistics {
+                    interface: interface.to_string(),
+                    ip,
+                    replies,
+                    rtt: RttSummary::from_samples(&rtts),
+                })
+                .collect(),
+            send_rate: report.send_rate(),
+            duration: report
+                .finished_at
+                .duration_since(report.started_at)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ScanStatistics {
+    /// Returns the statistics of the address `ip` on `interface`, if it answered there.
+    pub fn host(&self, interface: &str, ip: Ipv4Addr) -> Option<&HostStatistics> {
+        self.hosts
+            .binary_search_by(|host| (host.interface.as_str(), host.ip).cmp(&(interface, ip)))
+            .ok()
+            .map(|index| &self.hosts[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::fixtures::sample_report;
+
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn test_rtt_summary() {
+        let samples = millis(&(1..=20).rev().collect::<Vec<_>>());
+
+        let summary = RttSummary::from_samples(&samples).unwrap();
+
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.avg, Duration::from_micros(10_500));
+        assert_eq!(summary.max, Duration::from_millis(20));
+        assert_eq!(summary.p95, Duration::from_millis(19));
+        assert_eq!(RttSummary::from_samples(&[]), None);
+        assert_eq!(RttSummary::from_samples(&millis(&[7])).unwrap().p95, Duration::from_millis(7));
+    }
+
+    #[test]
+    fn test_scan_statistics_from_report() {
+        let statistics = ScanStatistics::from(&sample_report());
+
+        assert_eq!(statistics.packets_sent, 254);
+        assert_eq!(statistics.replies, 2);
+        assert_eq!(statistics.duplicates, 1);
+        assert_eq!(statistics.targets, 254);
+        assert_eq!(statistics.unanswered, 253);
+        assert_eq!(statistics.rtt.unwrap().max, Duration::from_micros(1500));
+        assert_eq!(statistics.send_rate, 506.0);
+        assert_eq!(statistics.duration, Duration::from_millis(2500));
+
+        let host = statistics.host("eth0", Ipv4Addr::new(192, 168, 0, 1)).unwrap();
+        assert_eq!(host.replies, 2);
+        assert_eq!(host.rtt.unwrap().avg, Duration::from_micros(1500));
+        assert_eq!(statistics.host("eth0", Ipv4Addr::new(192, 168, 0, 2)), None);
+    }
+
+    #[test]
+    fn test_host_rtt_includes_duplicate_replies() {
+        let mut report = sample_report();
+        report.hosts[1].rtt = Some(Duration::from_micros(2500));
+
+        let statistics = ScanStatistics::from(&report);
+
+        let rtt = statistics.host("eth0", Ipv4Addr::new(192, 168, 0, 1)).unwrap().rtt.unwrap();
+        assert_eq!(rtt.min, Duration::from_micros(1500));
+        assert_eq!(rtt.avg, Duration::from_micros(2000));
+        assert_eq!(rtt.max, Duration::from_micros(2500));
+    }
+
+    #[test]
+    fn test_host_statistics_of_merged_report_are_per_interface() {
+        let first = sample_report();
+        let mut second = sample_report();
+        second.interface = String::from("eth1");
+        second.hosts.truncate(1);
+        second.hosts[0].interface = String::from("eth1");
+        second.hosts[0].rtt = Some(Duration::from_micros(4000));
+        let report = ScanReport::merge(vec![first, second]).unwrap();
+
+        let statistics = ScanStatistics::from(&report);
+
+        let ip = Ipv4Addr::new(192, 168, 0, 1);
+        assert_eq!(statistics.hosts.len(), 2);
+        let eth0 = statistics.host("eth0", ip).unwrap();
+        assert_eq!(eth0.replies, 2);
+        assert_eq!(eth0.rtt.unwrap().max, Duration::from_micros(1500));
+        let eth1 = statistics.host("eth1", ip).unwrap();
+        assert_eq!(eth1.replies, 1);
+        assert_eq!(eth1.rtt.unwrap().avg, Duration::from_micros(4000));
+        assert_eq!(statistics.host("eth2", ip), None);
+    }
+}
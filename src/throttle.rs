@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+/// A token bucket refilled at a constant rate.
+///
+/// The bucket starts full, so up to `capacity` tokens can be taken at once before the
+/// refill rate takes over.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket refilled with `rate` tokens per second.
+    pub fn new(rate: f64, capacity: f64, now: Instant) -> Self {
+        TokenBucket {
+            rate,
+            capacity,
+            tokens: capacity,
+            refilled_at: now,
+        }
+    }
+
+    /// Returns the instant at which `cost` tokens are available.
+    pub fn ready_at(&mut self, cost: f64, now: Instant) -> Instant {
+        self.refill(now);
+        if self.tokens >= cost {
+            return now;
+        }
+        now + Duration::from_secs_f64((cost - self.tokens) / self.rate)
+    }
+
+    /// Takes `cost` tokens from the bucket, the balance may go negative.
+    pub fn consume(&mut self, cost: f64, now: Instant) {
+        self.refill(now);
+        self.tokens -= cost;
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.refilled_at = self.refilled_at.max(now);
+    }
+}
+
+/// Caps the packet rate and the bandwidth used by the ARP requests of a scan.
+///
+/// Each limit is a token bucket holding 10 ms worth of traffic (at least one frame), so
+/// short stalls of the receive loop can be caught up without exceeding the average rate.
+#[derive(Debug, Clone)]
+pub struct SendLimiter {
+    packets: Option<TokenBucket>,
+    bits: Option<TokenBucket>,
+    frame_bits: f64,
+}
+
+impl SendLimiter {
+    /// Creates a limiter for frames of `frame_length` bytes on the wire.
+    ///
+    /// # Parameters
+    ///
+    /// - `rate`: The maximum number of packets per second, if any.
+    /// - `bandwidth`: The maximum number of bits per second, if any.
+    /// - `frame_length`: The size of one frame on the wire, in bytes.
+    /// - `now`: The start of the scan.
+    pub fn new(rate: Option<u64>, bandwidth: Option<u64>, frame_length: usize, now: Instant) -> Self {
+        let frame_bits = (frame_length * 8) as f64;
+        let packets = rate.map(|rate| {
+            let rate = rate as f64;
+            TokenBucket::new(rate, (rate / 100.0).max(1.0), now)
+        });
+        let bits = bandwidth.map(|bandwidth| {
+            let bandwidth = bandwidth as f64;
+            TokenBucket::new(bandwidth, (bandwidth / 100.0).max(frame_bits), now)
+        });
+
+        SendLimiter {
+            packets,
+            bits,
+            frame_bits,
+        }
+    }
+
+    /// Returns the instant at which the next frame may be sent.
+    pub fn ready_at(&mut self, now: Instant) -> Instant {
+        let packets = self.packets.as_mut().map_or(now, |bucket| bucket.ready_at(1.0, now));
+        let bits = self
+            .bits
+            .as_mut()
+            .map_or(now, |bucket| bucket.ready_at(self.frame_bits, now));
+        packets.max(bits)
+    }
+
+    /// Records that a frame was sent at `now`.
+    pub fn consume(&mut self, now: Instant) {
+        if let Some(bucket) = self.packets.as_mut() {
+            bucket.consume(1.0, now);
+        }
+        if let Some(bucket) = self.bits.as_mut() {
+            bucket.consume(self.frame_bits, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_allows_burst_then_waits_for_refill() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, 2.0, start);
+
+        bucket.consume(1.0, start);
+        assert_eq!(bucket.ready_at(1.0, start), start);
+        bucket.consume(1.0, start);
+
+        assert_eq!(bucket.ready_at(1.0, start), start + Duration::from_millis(100));
+        assert_eq!(
+            bucket.ready_at(1.0, start + Duration::from_millis(100)),
+            start + Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn test_token_bucket_does_not_exceed_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, 1.0, start);
+        let later = start + Duration::from_secs(60);
+
+        bucket.consume(1.0, later);
+
+        assert_eq!(bucket.ready_at(1.0, later), later + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_send_limiter_without_limits_is_always_ready() {
+        let now = Instant::now();
+        let mut limiter = SendLimiter::new(None, None, 60, now);
+
+        for _ in 0..1000 {
+            limiter.consume(now);
+        }
+
+        assert_eq!(limiter.ready_at(now), now);
+    }
+
+    #[test]
+    fn test_send_limiter_uses_the_strictest_limit() {
+        let start = Instant::now();
+        // 4800 bit/s is 10 frames of 60 bytes per second, stricter than 100 packets per second.
+        let mut limiter = SendLimiter::new(Some(100), Some(4800), 60, start);
+
+        limiter.consume(start);
+
+        assert_eq!(limiter.ready_at(start), start + Duration::from_millis(100));
+    }
+}
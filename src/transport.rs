@@ -0,0 +1,196 @@
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
+
+use pnet::datalink::{Channel, Config, DataLinkReceiver, DataLinkSender, NetworkInterface};
+
+use crate::net::InterfaceError;
+
+pub const DATALINK_RCV_TIMEOUT: u64 = 1;
+
+/// A datalink channel able to send and receive raw Ethernet frames.
+///
+/// `net::arp_scan` only talks to the network through this trait, so scans can run
+/// against `PnetTransport` (an AF_PACKET socket, root only) or an in-memory fake.
+#[cfg_attr(test, mockall::automock)]
+pub trait Transport {
+    /// Returns the interface the frames are sent from.
+    fn interface(&self) -> &NetworkInterface;
+
+    /// Sends one Ethernet frame.
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error>;
+
+    /// Waits up to `timeout` for the next Ethernet frame.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if no frame arrived before `timeout`.
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error>;
+}
+
+/// A `Transport` over a pnet datalink channel (AF_PACKET socket on Linux).
+pub struct PnetTransport {
+    interface: NetworkInterface,
+    sender: Box<dyn DataLinkSender>,
+    receiver: Box<dyn DataLinkReceiver>,
+}
+
+impl PnetTransport {
+    /// Opens an Ethernet datalink channel on `interface`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the transport, or an `std::io::Error` if the channel cannot
+    /// be opened (usually because of missing privileges) or is not an Ethernet channel.
+    pub fn open(interface: &NetworkInterface) -> Result<Self, Error> {
+        let (sender, receiver) = match pnet::datalink::channel(interface, get_channel_config()) {
+            Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+            Ok(_) => Err(InterfaceError::NotEthernet(interface.name.clone()))?,
+            Err(e) => Err(e)?,
+        };
+
+        Ok(PnetTransport {
+            interface: interface.clone(),
+            sender,
+            receiver,
+        })
+    }
+}
+
+impl Transport for PnetTransport {
+    fn interface(&self) -> &NetworkInterface {
+        &self.interface
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error> {
+        self.sender.send_to(frame, None).unwrap_or(Ok(()))
+    }
+
+    /// The receiver wakes up at least every `DATALINK_RCV_TIMEOUT` milliseconds, so a read
+    /// timeout is just an idle tick used to check `timeout`.
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.receiver.next() {
+                Ok(frame) => return Ok(Some(frame.to_vec())),
+                Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {
+                    if Instant::now() >= deadline {
+                        return Ok(None);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn get_channel_config() -> Config {
+    Config {
+        read_timeout: Some(Duration::from_millis(DATALINK_RCV_TIMEOUT)),
+        ..Config::default()
+    }
+}
+
+#[cfg(test)]
+pub use fake::{arp_reply, FakeTransport};
+
+#[cfg(test)]
+mod fake {
+    use std::collections::VecDeque;
+    use std::net::Ipv4Addr;
+
+    use pnet::datalink::MacAddr;
+    use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
+    use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+    use pnet::packet::{MutablePacket, Packet};
+
+    use super::*;
+
+    /// An in-memory `Transport` where a fixed set of hosts answers ARP requests.
+    pub struct FakeTransport {
+        interface: NetworkInterface,
+        hosts: Vec<(Ipv4Addr, MacAddr)>,
+        inbox: VecDeque<Vec<u8>>,
+        pub sent: Vec<Vec<u8>>,
+    }
+
+    impl FakeTransport {
+        pub fn new(interface: NetworkInterface, hosts: Vec<(Ipv4Addr, MacAddr)>) -> Self {
+            FakeTransport {
+                interface,
+                hosts,
+                inbox: VecDeque::new(),
+                sent: Vec::new(),
+            }
+        }
+
+        /// Queues a frame that will be returned by the next `recv`.
+        pub fn push_frame(&mut self, frame: Vec<u8>) {
+            self.inbox.push_back(frame);
+        }
+
+        /// Returns the target addresses of the ARP requests sent so far.
+        pub fn sent_targets(&self) -> Vec<Ipv4Addr> {
+            self.sent
+                .iter()
+                .filter_map(|frame| EthernetPacket::new(frame))
+                .filter_map(|ethernet| ArpPacket::new(ethernet.payload()).map(|arp| arp.get_target_proto_addr()))
+                .collect()
+        }
+    }
+
+    impl Transport for FakeTransport {
+        fn interface(&self) -> &NetworkInterface {
+            &self.interface
+        }
+
+        fn send(&mut self, frame: &[u8]) -> Result<(), Error> {
+            self.sent.push(frame.to_vec());
+
+            let ethernet = EthernetPacket::new(frame).ok_or(ErrorKind::InvalidInput)?;
+            let request = ArpPacket::new(ethernet.payload()).ok_or(ErrorKind::InvalidInput)?;
+            let answers = self
+                .hosts
+                .iter()
+                .filter(|(ip, _)| *ip == request.get_target_proto_addr());
+            for (ip, mac) in answers {
+                let reply = arp_reply(
+                    (*mac, *ip),
+                    (request.get_sender_hw_addr(), request.get_sender_proto_addr()),
+                );
+                self.inbox.push_back(reply);
+            }
+            Ok(())
+        }
+
+        fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+            match self.inbox.pop_front() {
+                Some(frame) => Ok(Some(frame)),
+                None => {
+                    std::thread::sleep(timeout);
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Builds an ARP reply frame from `sender` to `target`.
+    pub fn arp_reply(sender: (MacAddr, Ipv4Addr), target: (MacAddr, Ipv4Addr)) -> Vec<u8> {
+        let mut frame = vec![0u8; 42];
+        let mut ethernet = MutableEthernetPacket::new(&mut frame).unwrap();
+        ethernet.set_destination(target.0);
+        ethernet.set_source(sender.0);
+        ethernet.set_ethertype(EtherTypes::Arp);
+
+        let mut arp = MutableArpPacket::new(ethernet.payload_mut()).unwrap();
+        arp.set_hardware_type(ArpHardwareTypes::Ethernet);
+        arp.set_protocol_type(EtherTypes::Ipv4);
+        arp.set_hw_addr_len(6);
+        arp.set_proto_addr_len(4);
+        arp.set_operation(ArpOperations::Reply);
+        arp.set_sender_hw_addr(sender.0);
+        arp.set_sender_proto_addr(sender.1);
+        arp.set_target_hw_addr(target.0);
+        arp.set_target_proto_addr(target.1);
+        frame
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+use pnet::datalink::MacAddr;
+
+use crate::report::ScanReport;
+
+/// OUI files looked up when `--oui-file` is not given, in order.
+pub const DEFAULT_OUI_FILES: [&str; 3] = [
+    "/usr/share/arp-scan/ieee-oui.txt",
+    "/usr/share/ieee-data/oui.txt",
+    "/usr/share/misc/oui.txt",
+];
+
+/// Maps the OUI (first three bytes of a MAC address) to the name of the vendor.
+#[derive(Debug, Default)]
+pub struct VendorDatabase {
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl VendorDatabase {
+    /// Parses an OUI list.
+    ///
+    /// Both the arp-scan format (`001122<TAB>Vendor`) and the IEEE format
+    /// (`00-11-22   (hex)<TAB>Vendor` or `001122   (base 16)<TAB>Vendor`) are understood.
+    /// Empty lines, comments and lines without a 24-bit prefix are skipped.
+    pub fn parse(content: &str) -> Self {
+        let vendors = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                if line.starts_with('#') {
+                    return None;
+                }
+                let (prefix, vendor) = line.split_once(char::is_whitespace)?;
+                let prefix = parse_prefix(prefix)?;
+                let vendor = vendor.trim();
+                let vendor = vendor
+                    .strip_prefix("(hex)")
+                    .or_else(|| vendor.strip_prefix("(base 16)"))
+                    .unwrap_or(vendor)
+                    .trim();
+                (!vendor.is_empty()).then(|| (prefix, vendor.to_string()))
+            })
+            .collect();
+
+        VendorDatabase { vendors }
+    }
+
+    /// Loads an OUI list from `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Loads the first readable file of `DEFAULT_OUI_FILES`, or an empty database.
+    pub fn load_default() -> Self {
+        DEFAULT_OUI_FILES
+            .iter()
+            .find_map(|path| Self::load(path).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the vendor of `mac`, if its OUI is known.
+    pub fn lookup(&self, mac: MacAddr) -> Option<&str> {
+        self.vendors.get(&[mac.0, mac.1, mac.2]).map(String::as_str)
+    }
+
+    /// Sets the vendor of every host of `report`.
+    pub fn annotate(&self, report: &mut ScanReport) {
+        for host in report.hosts.iter_mut() {
+            host.vendor = self.lookup(host.mac).map(String::from);
+        }
+    }
+}
+
+/// Parses a 24-bit OUI written as `001122`, `00-11-22` or `00:11:22`.
+fn parse_prefix(prefix: &str) -> Option<[u8; 3]> {
+    let digits: String = prefix.chars().filter(|c| *c != '-' && *c != ':').collect();
+    if digits.len() != 6 {
+        return None;
+    }
+
+    let value = u32::from_str_radix(&digits, 16).ok()?;
+    let [_, a, b, c] = value.to_be_bytes();
+    Some([a, b, c])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arp_scan_format() {
+        let database = VendorDatabase::parse("# comment\n001122\tAcme Networks\n\nFCFBFB\tCisco Systems, Inc\n");
+
+        assert_eq!(database.lookup(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55)), Some("Acme Networks"));
+        assert_eq!(database.lookup(MacAddr::new(0xfc, 0xfb, 0xfb, 0, 0, 1)), Some("Cisco Systems, Inc"));
+        assert_eq!(database.lookup(MacAddr::new(0x02, 0, 0, 0, 0, 1)), None);
+    }
+
+    #[test]
+    fn test_parse_ieee_format() {
+        let database = VendorDatabase::parse(
+            "00-11-22   (hex)\t\tAcme Networks\n001122     (base 16)\t\tAcme Networks\n\t\t\t\tSomewhere 1\n",
+        );
+
+        assert_eq!(database.lookup(MacAddr::new(0x00, 0x11, 0x22, 0, 0, 0)), Some("Acme Networks"));
+        assert_eq!(database.vendors.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_prefix_rejects_longer_prefixes() {
+        assert_eq!(parse_prefix("00:1B:C5"), Some([0x00, 0x1b, 0xc5]));
+        assert_eq!(parse_prefix("00:1B:C5:00:00:00/36"), None);
+        assert_eq!(parse_prefix("vendor"), None);
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::{self, Write};
+
+use crate::options::{CsvColumn, CsvOptions};
+use crate::report::{DiscoveredHost, ScanReport};
+use crate::stats::{HostStatistics, RttSummary, ScanStatistics};
+use crate::view::document::{as_millis, format_time, InterfaceRecord};
+
+/// Writes the discovered hosts of a scan report as RFC 4180 CSV.
+///
+/// One record is written per host with the columns selected in `options`, preceded by a
+/// header row unless disabled. Records end with CRLF, and fields containing the delimiter,
+/// a double quote or a line break are quoted. The `replies` and `rtt_*` columns hold the
+/// statistics of the host address on its interface over the whole scan, the scan-wide
+/// statistics are written apart by `write_statistics`.
+///
+/// # Parameters
+///
+/// - `writer`: The destination of the CSV records.
+/// - `report`: The `ScanReport` returned by `net::arp_scan`.
+/// - `options`: The columns, header and delimiter settings.
+///
+/// # Examples
+///
+/// ```
+/// use csv::write_scan_report;
+///
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// write_scan_report(&mut std::io::stdout(), &report, &options.csv)?;
+/// ```
+pub fn write_scan_report<W: Write>(
+    writer: &mut W,
+    report: &ScanReport,
+    options: &CsvOptions,
+) -> Result<(), io::Error> {
+    if options.header {
+        let header: Vec<String> = options.columns.iter().map(|column| column.to_string()).collect();
+        write_record(writer, &header, options.delimiter)?;
+    }
+
+    let statistics = ScanStatistics::from(report);
+    for host in &report.hosts {
+        let host_statistics = statistics.host(&host.interface, host.ip);
+        let record: Vec<String> = options
+            .columns
+            .iter()
+            .map(|column| get_field(host, host_statistics, *column))
+            .collect();
+        write_record(writer, &record, options.delimiter)?;
+    }
+    writer.flush()
+}
+
+/// Writes the statistics of a scan report as RFC 4180 CSV, one `statistic,value` record
+/// per statistic.
+///
+/// The per-host records of `write_scan_report` cannot hold scan-wide figures, so they go
+/// to this companion output (see `--stats`): packets sent, replies, duplicates,
+/// unsolicited replies, targets, unanswered targets, loss, the RTT distribution in
+/// milliseconds, the send rate in packets per second and the duration in milliseconds.
+/// RTT fields are empty when no reply was timed. Only the header and delimiter settings of
+/// `options` apply.
+pub fn write_statistics<W: Write>(
+    writer: &mut W,
+    report: &ScanReport,
+    options: &CsvOptions,
+) -> Result<(), io::Error> {
+    if options.header {
+        write_record(writer, &[String::from("statistic"), String::from("value")], options.delimiter)?;
+    }
+
+    let statistics = ScanStatistics::from(report);
+    let loss = match statistics.targets {
+        0 => 0.0,
+        targets => statistics.unanswered as f64 * 100.0 / targets as f64,
+    };
+    let rtt = |summary: fn(&RttSummary) -> std::time::Duration| {
+        statistics
+            .rtt
+            .as_ref()
+            .map(|rtt| format!("{:.3}", as_millis(summary(rtt))))
+            .unwrap_or_default()
+    };
+    let records = [
+        ("packets_sent", statistics.packets_sent.to_string()),
+        ("replies", statistics.replies.to_string()),
+        ("duplicates", statistics.duplicates.to_string()),
+        ("unsolicited", statistics.unsolicited.to_string()),
+        ("targets", statistics.targets.to_string()),
+        ("unanswered", statistics.unanswered.to_string()),
+        ("loss_percent", format!("{:.1}", loss)),
+        ("rtt_min_ms", rtt(|rtt| rtt.min)),
+        ("rtt_avg_ms", rtt(|rtt| rtt.avg)),
+        ("rtt_max_ms", rtt(|rtt| rtt.max)),
+        ("rtt_p95_ms", rtt(|rtt| rtt.p95)),
+        ("send_rate", format!("{:.1}", statistics.send_rate)),
+        ("duration_ms", format!("{:.3}", as_millis(statistics.duration))),
+    ];
+    for (name, value) in records {
+        write_record(writer, &[name.to_string(), value], options.delimiter)?;
+    }
+    writer.flush()
+}
+
+/// Displays the result of an ARP scan as CSV on the standard output.
+pub fn show_scan_report(report: &ScanReport, options: &CsvOptions) -> Result<(), io::Error> {
+    write_scan_report(&mut io::stdout().lock(), report, options)
+}
+
+/// Writes a list of network interfaces as RFC 4180 CSV.
+///
+/// The columns are fixed: `index`, `name`, `mac`, `ipv4`, `ipv6`, `flags`, `mtu`,
+/// `oper_state` and `available`. Addresses and flags are separated by spaces. Only the header and delimiter
+/// settings of `options` apply.
+pub fn write_interfaces<W: Write>(
+    writer: &mut W,
+    interfaces: &[InterfaceRecord],
+    options: &CsvOptions,
+) -> Result<(), io::Error> {
+    if options.header {
+        let header = [
+            "index",
+            "name",
+            "mac",
+            "ipv4",
+            "ipv6",
+            "flags",
+            "mtu",
+            "oper_state",
+            "available",
+        ].map(String::from);
+        write_record(writer, &header, options.delimiter)?;
+    }
+
+    for interface in interfaces {
+        let record = [
+            interface.index.to_string(),
+            interface.name.clone(),
+            interface.mac.clone().unwrap_or_default(),
+            interface.ipv4.join(" "),
+            interface.ipv6.join(" "),
+            interface.flags.join(" "),
+            interface.mtu.map(|mtu| mtu.to_string()).unwrap_or_default(),
+            interface.oper_state.clone().unwrap_or_default(),
+            interface.available.to_string(),
+        ];
+        write_record(writer, &record, options.delimiter)?;
+    }
+    writer.flush()
+}
+
+/// Displays a list of network interfaces as CSV on the standard output.
+pub fn show_interfaces(interfaces: &[InterfaceRecord], options: &CsvOptions) -> Result<(), io::Error> {
+    write_interfaces(&mut io::stdout().lock(), interfaces, options)
+}
+
+fn get_field(host: &DiscoveredHost, statistics: Option<&HostStatistics>, column: CsvColumn) -> String {
+    let rtt = |summary: fn(&RttSummary) -> std::time::Duration| {
+        statistics
+            .and_then(|statistics| statistics.rtt.as_ref())
+            .map(|rtt| format!("{:.3}", as_millis(summary(rtt))))
+            .unwrap_or_default()
+    };
+    match column {
+        CsvColumn::Ip => host.ip.to_string(),
+        CsvColumn::Mac => host.mac.to_string(),
+        CsvColumn::Vendor => host.vendor.clone().unwrap_or_default(),
+        CsvColumn::Rtt => host
+            .rtt
+            .map(|rtt| format!("{:.3}", as_millis(rtt)))
+            .unwrap_or_default(),
+        CsvColumn::Interface => host.interface.clone(),
+        CsvColumn::ReplyTime => format_time(host.reply_time),
+        CsvColumn::Duplicate => host.duplicate.to_string(),
+        CsvColumn::Unsolicited => host.unsolicited.to_string(),
+        CsvColumn::Replies => statistics.map_or(0, |statistics| statistics.replies).to_string(),
+        CsvColumn::RttMin => rtt(|rtt| rtt.min),
+        CsvColumn::RttAvg => rtt(|rtt| rtt.avg),
+        CsvColumn::RttMax => rtt(|rtt| rtt.max),
+        CsvColumn::RttP95 => rtt(|rtt| rtt.p95),
+    }
+}
+
+fn write_record<W: Write>(writer: &mut W, fields: &[String], delimiter: char) -> Result<(), io::Error> {
+    let record: Vec<String> = fields
+        .iter()
+        .map(|field| quote_field(field, delimiter))
+        .collect();
+    write!(writer, "{}\r\n", record.join(&delimiter.to_string()))
+}
+
+/// Quotes a field if it contains the delimiter, a double quote or a line break.
+fn quote_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::fixtures::sample_report;
+    use crate::view::document::fixtures::sample_interfaces;
+
+    use super::*;
+
+    fn csv_options(columns: Vec<CsvColumn>, header: bool, delimiter: char) -> CsvOptions {
+        CsvOptions {
+            columns,
+            header,
+            delimiter,
+        }
+    }
+
+    fn render(report: &ScanReport, options: &CsvOptions) -> String {
+        let mut output = Vec::new();
+        write_scan_report(&mut output, report, options).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_write_scan_report_default_columns() {
+        let mut report = sample_report();
+        report.hosts[0].vendor = Some(String::from("Acme, Inc."));
+
+        let output = render(&report, &CsvOptions::default());
+
+        assert_eq!(
+            output,
+            "ip,mac,vendor,rtt\r\n\
+             192.168.0.1,00:11:22:33:44:55,\"Acme, Inc.\",1.500\r\n\
+             192.168.0.1,00:11:22:33:44:66,,\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_scan_report_selected_columns_without_header() {
+        let options = csv_options(vec![CsvColumn::Duplicate, CsvColumn::Ip], false, ';');
+
+        let output = render(&sample_report(), &options);
+
+        assert_eq!(output, "false;192.168.0.1\r\ntrue;192.168.0.1\r\n");
+    }
+
+    #[test]
+    fn test_write_scan_report_statistics_columns() {
+        let options = csv_options(vec![CsvColumn::Mac, CsvColumn::Replies, CsvColumn::RttP95], true, ',');
+
+        let output = render(&sample_report(), &options);
+
+        assert_eq!(
+            output,
+            "mac,replies,rtt_p95\r\n\
+             00:11:22:33:44:55,2,1.500\r\n\
+             00:11:22:33:44:66,2,1.500\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_statistics() {
+        let mut output = Vec::new();
+
+        write_statistics(&mut output, &sample_report(), &csv_options(vec![], true, ';')).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "statistic;value\r\n\
+             packets_sent;254\r\n\
+             replies;2\r\n\
+             duplicates;1\r\n\
+             unsolicited;0\r\n\
+             targets;254\r\n\
+             unanswered;253\r\n\
+             loss_percent;99.6\r\n\
+             rtt_min_ms;1.500\r\n\
+             rtt_avg_ms;1.500\r\n\
+             rtt_max_ms;1.500\r\n\
+             rtt_p95_ms;1.500\r\n\
+             send_rate;506.0\r\n\
+             duration_ms;2500.000\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_interfaces() {
+        let mut output = Vec::new();
+
+        write_interfaces(&mut output, &sample_interfaces(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "index,name,mac,ipv4,ipv6,flags,mtu,oper_state,available\r\n\
+             2,eth0,02:00:00:00:00:01,192.168.0.10/24,fe80::1/64,UP BROADCAST MULTICAST,1500,up,true\r\n"
+        );
+    }
+
+    #[test]
+    fn test_quote_field() {
+        assert_eq!(quote_field("plain", ','), "plain");
+        assert_eq!(quote_field("a;b", ';'), "\"a;b\"");
+        assert_eq!(quote_field("a,b", ';'), "a,b");
+        assert_eq!(quote_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote_field("two\nlines", ','), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,394 @@
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime};
+
+use pnet::datalink::NetworkInterface;
+use serde::Serialize;
+
+use crate::net::{get_flag_names, get_mtu, get_oper_state, get_unavailable_reason};
+use crate::report::{ArpFields, DiscoveredHost, ScanEvent, ScanReport, ScanSegment};
+use crate::stats::{HostStatistics, RttSummary, ScanStatistics};
+
+/// Version of the structured scan document.
+///
+/// Bump the major version on any change that renames or removes a field or changes its
+/// meaning, and the minor version when fields are only added.
+pub const SCHEMA_VERSION: &str = "1.0";
+
+/// Version of the interfaces document, bumped with the same rules as `SCHEMA_VERSION`.
+pub const INTERFACES_SCHEMA_VERSION: &str = "1.0";
+
+/// The structured representation of a `ScanReport`, shared by the JSON and YAML outputs.
+#[derive(Debug, Serialize)]
+pub struct ScanDocument {
+    pub schema_version: &'static str,
+    pub scan: ScanMetadata,
+    pub hosts: Vec<HostRecord>,
+    pub statistics: StatisticsRecord,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanMetadata {
+    pub interface: String,
+    pub source_ip: Ipv4Addr,
+    pub source_mac: Option<String>,
+    pub network: String,
+    pub profile: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    /// Every interface and network of the scan, the fields above describe the first one.
+    pub segments: Vec<SegmentRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SegmentRecord {
+    pub interface: String,
+    pub source_ip: Ipv4Addr,
+    pub source_mac: Option<String>,
+    pub network: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostRecord {
+    pub ip: Ipv4Addr,
+    pub mac: String,
+    pub vendor: Option<String>,
+    pub interface: String,
+    pub reply_time: String,
+    pub rtt_ms: Option<f64>,
+    pub duplicate: bool,
+    pub unsolicited: bool,
+    pub arp: ArpRecord,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatisticsRecord {
+    pub packets_sent: u64,
+    pub replies: u64,
+    pub duplicates: u64,
+    pub unsolicited: u64,
+    pub targets: u64,
+    pub unanswered: u64,
+    pub unanswered_ips: Vec<Ipv4Addr>,
+    pub rtt: Option<RttRecord>,
+    pub hosts: Vec<HostStatisticsRecord>,
+    pub send_rate: f64,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RttRecord {
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub p95_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostStatisticsRecord {
+    pub interface: String,
+    pub ip: Ipv4Addr,
+    pub replies: u64,
+    pub rtt: Option<RttRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArpRecord {
+    pub hardware_type: u16,
+    pub protocol_type: u16,
+    pub hw_addr_len: u8,
+    pub proto_addr_len: u8,
+    pub operation: u16,
+    pub sender_hw_addr: String,
+    pub sender_proto_addr: Ipv4Addr,
+    pub target_hw_addr: String,
+    pub target_proto_addr: Ipv4Addr,
+}
+
+impl From<&ScanReport> for ScanDocument {
+    fn from(report: &ScanReport) -> Self {
+        ScanDocument {
+            schema_version: SCHEMA_VERSION,
+            scan: ScanMetadata::from(report),
+            hosts: report.hosts.iter().map(HostRecord::from).collect(),
+            statistics: StatisticsRecord::from(report),
+        }
+    }
+}
+
+impl From<&ScanReport> for StatisticsRecord {
+    fn from(report: &ScanReport) -> Self {
+        let statistics = ScanStatistics::from(report);
+        StatisticsRecord {
+            packets_sent: statistics.packets_sent,
+            replies: statistics.replies,
+            duplicates: statistics.duplicates,
+            unsolicited: statistics.unsolicited,
+            targets: statistics.targets,
+            unanswered: statistics.unanswered,
+            unanswered_ips: report.unanswered.clone(),
+            rtt: statistics.rtt.map(RttRecord::from),
+            hosts: statistics.hosts.iter().map(HostStatisticsRecord::from).collect(),
+            send_rate: statistics.send_rate,
+            duration_ms: as_millis(statistics.duration),
+        }
+    }
+}
+
+impl From<RttSummary> for RttRecord {
+    fn from(rtt: RttSummary) -> Self {
+        RttRecord {
+            min_ms: as_millis(rtt.min),
+            avg_ms: as_millis(rtt.avg),
+            max_ms: as_millis(rtt.max),
+            p95_ms: as_millis(rtt.p95),
+        }
+    }
+}
+
+impl From<&HostStatistics> for HostStatisticsRecord {
+    fn from(host: &HostStatistics) -> Self {
+        HostStatisticsRecord {
+            interface: host.interface.clone(),
+            ip: host.ip,
+            replies: host.replies,
+            rtt: host.rtt.map(RttRecord::from),
+        }
+    }
+}
+
+impl From<&ScanReport> for ScanMetadata {
+    fn from(report: &ScanReport) -> Self {
+        ScanMetadata {
+            interface: report.interface.clone(),
+            source_ip: report.source_ip,
+            source_mac: report.source_mac.map(|mac| mac.to_string()),
+            network: report.network.to_string(),
+            profile: report.profile.to_string(),
+            started_at: format_time(report.started_at),
+            finished_at: format_time(report.finished_at),
+            packets_sent: report.packets_sent,
+            packets_received: report.packets_received,
+            segments: report.segments.iter().map(SegmentRecord::from).collect(),
+        }
+    }
+}
+
+impl From<&ScanSegment> for SegmentRecord {
+    fn from(segment: &ScanSegment) -> Self {
+        SegmentRecord {
+            interface: segment.interface.clone(),
+            source_ip: segment.source_ip,
+            source_mac: segment.source_mac.map(|mac| mac.to_string()),
+            network: segment.network.to_string(),
+        }
+    }
+}
+
+impl From<&DiscoveredHost> for HostRecord {
+    fn from(host: &DiscoveredHost) -> Self {
+        HostRecord {
+            ip: host.ip,
+            mac: host.mac.to_string(),
+            vendor: host.vendor.clone(),
+            interface: host.interface.clone(),
+            reply_time: format_time(host.reply_time),
+            rtt_ms: host.rtt.map(as_millis),
+            duplicate: host.duplicate,
+            unsolicited: host.unsolicited,
+            arp: ArpRecord::from(&host.arp),
+        }
+    }
+}
+
+impl From<&ArpFields> for ArpRecord {
+    fn from(arp: &ArpFields) -> Self {
+        ArpRecord {
+            hardware_type: arp.hardware_type,
+            protocol_type: arp.protocol_type,
+            hw_addr_len: arp.hw_addr_len,
+            proto_addr_len: arp.proto_addr_len,
+            operation: arp.operation,
+            sender_hw_addr: arp.sender_hw_addr.to_string(),
+            sender_proto_addr: arp.sender_proto_addr,
+            target_hw_addr: arp.target_hw_addr.to_string(),
+            target_proto_addr: arp.target_proto_addr,
+        }
+    }
+}
+
+/// The structured list of network interfaces written by the `interfaces` subcommand.
+#[derive(Debug, Serialize)]
+pub struct InterfacesDocument<'a> {
+    pub schema_version: &'static str,
+    pub interfaces: &'a [InterfaceRecord],
+}
+
+#[derive(Debug, Serialize)]
+pub struct InterfaceRecord {
+    /// Index of the interface in the kernel.
+    pub index: u32,
+    pub name: String,
+    pub mac: Option<String>,
+    /// IPv4 addresses in CIDR notation.
+    pub ipv4: Vec<String>,
+    /// IPv6 addresses in CIDR notation.
+    pub ipv6: Vec<String>,
+    pub flags: Vec<&'static str>,
+    pub mtu: Option<u32>,
+    pub oper_state: Option<String>,
+    /// The interface can be scanned from, see `net::get_available_interfaces`.
+    pub available: bool,
+}
+
+impl InterfaceRecord {
+    /// Builds the record of `interface`, whose MTU and operational state were read apart.
+    pub fn new(interface: &NetworkInterface, mtu: Option<u32>, oper_state: Option<String>) -> Self {
+        let addresses = |ipv4: bool| {
+            interface
+                .ips
+                .iter()
+                .filter(|ip| ip.is_ipv4() == ipv4)
+                .map(|ip| ip.to_string())
+                .collect()
+        };
+
+        InterfaceRecord {
+            index: interface.index,
+            name: interface.name.clone(),
+            mac: interface.mac.map(|mac| mac.to_string()),
+            ipv4: addresses(true),
+            ipv6: addresses(false),
+            flags: get_flag_names(interface),
+            mtu,
+            oper_state,
+            available: get_unavailable_reason(interface).is_none(),
+        }
+    }
+}
+
+impl From<&NetworkInterface> for InterfaceRecord {
+    /// Reads the MTU and operational state of `interface` from the system.
+    fn from(interface: &NetworkInterface) -> Self {
+        InterfaceRecord::new(interface, get_mtu(&interface.name), get_oper_state(&interface.name))
+    }
+}
+
+impl<'a> From<&'a [InterfaceRecord]> for InterfacesDocument<'a> {
+    fn from(interfaces: &'a [InterfaceRecord]) -> Self {
+        InterfacesDocument {
+            schema_version: INTERFACES_SCHEMA_VERSION,
+            interfaces,
+        }
+    }
+}
+
+/// One line of the NDJSON output, tagged by its `event` field.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventRecord {
+    HostDiscovered {
+        time: String,
+        host: HostRecord,
+    },
+    Retry {
+        time: String,
+        ip: Ipv4Addr,
+        attempt: u32,
+    },
+    ScanFinished {
+        time: String,
+        schema_version: &'static str,
+        scan: ScanMetadata,
+        statistics: StatisticsRecord,
+    },
+}
+
+impl EventRecord {
+    /// Converts a scan event that happened at `time`.
+    pub fn new(event: &ScanEvent, time: SystemTime) -> Self {
+        let time = format_time(time);
+        match event {
+            ScanEvent::HostDiscovered(host) => EventRecord::HostDiscovered {
+                time,
+                host: HostRecord::from(*host),
+            },
+            ScanEvent::Retry { ip, attempt } => EventRecord::Retry {
+                time,
+                ip: *ip,
+                attempt: *attempt,
+            },
+            ScanEvent::Finished(report) => EventRecord::ScanFinished {
+                time,
+                schema_version: SCHEMA_VERSION,
+                scan: ScanMetadata::from(*report),
+                statistics: StatisticsRecord::from(*report),
+            },
+        }
+    }
+}
+
+/// Formats a timestamp as RFC 3339 in UTC with millisecond precision.
+pub fn format_time(time: SystemTime) -> String {
+    humantime::format_rfc3339_millis(time).to_string()
+}
+
+/// Converts a duration to fractional milliseconds.
+pub fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+pub mod fixtures {
+    use ipnetwork::IpNetwork;
+    use pnet::datalink::MacAddr;
+
+    use super::*;
+
+    /// Returns the record of an Ethernet interface with an IPv4 and an IPv6 address.
+    pub fn sample_interfaces() -> Vec<InterfaceRecord> {
+        let interface = NetworkInterface {
+            name: String::from("eth0"),
+            description: String::new(),
+            index: 2,
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, 0x01)),
+            ips: vec![
+                IpNetwork::V4("192.168.0.10/24".parse().unwrap()),
+                IpNetwork::V6("fe80::1/64".parse().unwrap()),
+            ],
+            flags: 0x1003,
+        };
+        vec![InterfaceRecord::new(&interface, Some(1500), Some(String::from("up")))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interface_record() {
+        let record = fixtures::sample_interfaces().remove(0);
+
+        assert_eq!(record.index, 2);
+        assert_eq!(record.mac.as_deref(), Some("02:00:00:00:00:01"));
+        assert_eq!(record.ipv4, vec!["192.168.0.10/24"]);
+        assert_eq!(record.ipv6, vec!["fe80::1/64"]);
+        assert_eq!(record.flags, vec!["UP", "BROADCAST", "MULTICAST"]);
+        assert_eq!(record.mtu, Some(1500));
+        assert!(record.available);
+    }
+
+    #[test]
+    fn test_format_time_is_rfc3339() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+
+        assert_eq!(format_time(time), "2023-11-14T22:13:20.123Z");
+    }
+
+    #[test]
+    fn test_as_millis_keeps_fraction() {
+        assert_eq!(as_millis(Duration::from_micros(1500)), 1.5);
+    }
+}
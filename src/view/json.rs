@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+
+use crate::report::ScanReport;
+use crate::view::document::{InterfaceRecord, InterfacesDocument, ScanDocument};
+
+/// Writes a scan report as a pretty-printed JSON document.
+///
+/// The document follows the versioned schema of `view::document::ScanDocument`:
+/// a `schema_version`, the `scan` metadata and the array of `hosts`.
+///
+/// # Parameters
+///
+/// - `writer`: The destination of the JSON document.
+/// - `report`: The `ScanReport` returned by `net::arp_scan`.
+///
+/// # Examples
+///
+/// ```
+/// use json::write_scan_report;
+///
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// write_scan_report(&mut std::io::stdout(), &report)?;
+/// ```
+pub fn write_scan_report<W: Write>(writer: &mut W, report: &ScanReport) -> Result<(), io::Error> {
+    serde_json::to_writer_pretty(&mut *writer, &ScanDocument::from(report))?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Displays the result of an ARP scan as JSON on the standard output.
+pub fn show_scan_report(report: &ScanReport) -> Result<(), io::Error> {
+    write_scan_report(&mut io::stdout().lock(), report)
+}
+
+/// Writes a list of network interfaces as a pretty-printed JSON document, see
+/// `view::document::InterfacesDocument`.
+pub fn write_interfaces<W: Write>(writer: &mut W, interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    serde_json::to_writer_pretty(&mut *writer, &InterfacesDocument::from(interfaces))?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Displays a list of network interfaces as JSON on the standard output.
+pub fn show_interfaces(interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    write_interfaces(&mut io::stdout().lock(), interfaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::report::fixtures::sample_report;
+    use crate::view::document::fixtures::sample_interfaces;
+    use crate::view::document::{INTERFACES_SCHEMA_VERSION, SCHEMA_VERSION};
+
+    use super::*;
+
+    #[test]
+    fn test_write_scan_report_json_document() {
+        let mut output = Vec::new();
+        write_scan_report(&mut output, &sample_report()).unwrap();
+
+        let document: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        assert_eq!(document["scan"]["interface"], "eth0");
+        assert_eq!(document["scan"]["source_mac"], "02:00:00:00:00:01");
+        assert_eq!(document["scan"]["network"], "192.168.0.0/24");
+        assert_eq!(document["scan"]["profile"], "default");
+        assert_eq!(document["scan"]["started_at"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(document["scan"]["packets_sent"], 254);
+        assert_eq!(document["scan"]["segments"][0]["interface"], "eth0");
+        assert_eq!(document["scan"]["segments"][0]["network"], "192.168.0.0/24");
+        assert_eq!(document["hosts"][0]["ip"], "192.168.0.1");
+        assert_eq!(document["hosts"][0]["mac"], "00:11:22:33:44:55");
+        assert_eq!(document["hosts"][0]["rtt_ms"], 1.5);
+        assert_eq!(document["hosts"][0]["arp"]["operation"], 2);
+        assert_eq!(document["hosts"][1]["duplicate"], true);
+        assert_eq!(document["hosts"][1]["rtt_ms"], Value::Null);
+        assert_eq!(document["statistics"]["duplicates"], 1);
+        assert_eq!(document["statistics"]["unanswered"], 253);
+        assert_eq!(document["statistics"]["unanswered_ips"][0], "192.168.0.2");
+        assert_eq!(document["statistics"]["rtt"]["p95_ms"], 1.5);
+        assert_eq!(document["statistics"]["hosts"][0]["replies"], 2);
+        assert_eq!(document["statistics"]["duration_ms"], 2500.0);
+    }
+
+    #[test]
+    fn test_write_interfaces_json_document() {
+        let mut output = Vec::new();
+        write_interfaces(&mut output, &sample_interfaces()).unwrap();
+
+        let document: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(document["schema_version"], INTERFACES_SCHEMA_VERSION);
+        assert_eq!(document["interfaces"][0]["index"], 2);
+        assert_eq!(document["interfaces"][0]["name"], "eth0");
+        assert_eq!(document["interfaces"][0]["ipv4"][0], "192.168.0.10/24");
+        assert_eq!(document["interfaces"][0]["flags"][0], "UP");
+        assert_eq!(document["interfaces"][0]["mtu"], 1500);
+        assert_eq!(document["interfaces"][0]["oper_state"], "up");
+        assert_eq!(document["interfaces"][0]["available"], true);
+    }
+}
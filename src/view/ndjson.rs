@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::report::{ScanEvent, ScanObserver};
+use crate::vendor::VendorDatabase;
+use crate::view::document::{EventRecord, InterfaceRecord};
+
+/// Streams the events of a running scan as newline-delimited JSON.
+///
+/// Every event is written as one `view::document::EventRecord` object on its own line
+/// and flushed right away, so the output can be piped to another tool while the scan
+/// is still running.
+///
+/// # Examples
+///
+/// ```
+/// use ndjson::NdjsonWriter;
+///
+/// let mut writer = NdjsonWriter::new(std::io::stdout(), &vendors);
+/// net::arp_scan_with_observer(&mut transport, &options, &mut writer)?;
+/// ```
+pub struct NdjsonWriter<'a, W: Write> {
+    writer: W,
+    vendors: &'a VendorDatabase,
+}
+
+impl<'a, W: Write> NdjsonWriter<'a, W> {
+    /// Creates a writer looking up the vendor of discovered hosts in `vendors`.
+    pub fn new(writer: W, vendors: &'a VendorDatabase) -> Self {
+        NdjsonWriter { writer, vendors }
+    }
+}
+
+impl<W: Write> ScanObserver for NdjsonWriter<'_, W> {
+    fn on_event(&mut self, event: &ScanEvent) -> Result<(), io::Error> {
+        let mut record = EventRecord::new(event, SystemTime::now());
+        if let (EventRecord::HostDiscovered { host: record, .. }, ScanEvent::HostDiscovered(host)) =
+            (&mut record, event)
+        {
+            record.vendor = self.vendors.lookup(host.mac).map(String::from);
+        }
+
+        serde_json::to_writer(&mut self.writer, &record)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+/// Writes one JSON object per network interface, each on its own line.
+pub fn write_interfaces<W: Write>(writer: &mut W, interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    for interface in interfaces {
+        serde_json::to_writer(&mut *writer, interface)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Displays a list of network interfaces as NDJSON on the standard output.
+pub fn show_interfaces(interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    write_interfaces(&mut io::stdout().lock(), interfaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use serde_json::Value;
+
+    use crate::report::fixtures::sample_report;
+    use crate::view::document::SCHEMA_VERSION;
+
+    use super::*;
+
+    #[test]
+    fn test_writes_one_event_per_line() {
+        let report = sample_report();
+        let vendors = VendorDatabase::parse("001122\tAcme Networks\n");
+        let mut output = Vec::new();
+
+        let mut writer = NdjsonWriter::new(&mut output, &vendors);
+        writer.on_event(&ScanEvent::HostDiscovered(&report.hosts[0])).unwrap();
+        writer.on_event(&ScanEvent::Retry { ip: Ipv4Addr::new(192, 168, 0, 2), attempt: 2 }).unwrap();
+        writer.on_event(&ScanEvent::Finished(&report)).unwrap();
+
+        let events: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["event"], "host_discovered");
+        assert_eq!(events[0]["host"]["ip"], "192.168.0.1");
+        assert_eq!(events[0]["host"]["vendor"], "Acme Networks");
+        assert_eq!(events[1]["event"], "retry");
+        assert_eq!(events[1]["ip"], "192.168.0.2");
+        assert_eq!(events[1]["attempt"], 2);
+        assert_eq!(events[2]["event"], "scan_finished");
+        assert_eq!(events[2]["schema_version"], SCHEMA_VERSION);
+        assert_eq!(events[2]["scan"]["packets_sent"], 254);
+        assert_eq!(events[2]["statistics"]["unanswered"], 253);
+    }
+}
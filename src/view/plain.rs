@@ -3,11 +3,20 @@ use std::io::Write;
 use pnet::datalink::{MacAddr, NetworkInterface};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+use crate::net::{get_flag_names, get_mtu, get_oper_state, get_unavailable_reason};
+use crate::report::{DiscoveredHost, ScanReport};
+use crate::stats::ScanStatistics;
+use crate::view::document::as_millis;
+
+/// Color of the interfaces that cannot be scanned from, listed with `--show-all-interfaces`.
+const GREYED: Color = Color::Ansi256(8);
+
 /// Displays a formatted list of available network interfaces.
 ///
 /// This function takes a vector of references to `NetworkInterface` and prints a formatted
-/// list of information for each interface, including the interface ID, name, MAC address,
-/// IPv4 and IPv6 counts, and interface flags.
+/// list of information for each interface, including the interface index, name, MAC address,
+/// IPv4 and IPv6 addresses, MTU, operational state and interface flags. Interfaces that
+/// cannot be scanned from (down, without IPv4 address or ARP) are greyed out.
 ///
 /// # Parameters
 ///
@@ -33,24 +42,28 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
         .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
     writeln!(&mut stdout, "Available network interfaces:")?;
 
-    for (id, interface) in interfaces.iter().enumerate() {
-        // Id - first column
-        colorize_and_write(&mut stdout, Color::Yellow, &format!("{}:", id));
+    for interface in interfaces {
+        // Interfaces that cannot be scanned from are greyed out
+        let greyed = get_unavailable_reason(interface).is_some();
+        let color = |color: Color| if greyed { GREYED } else { color };
+
+        // Kernel index - first column, the number accepted by --interface
+        colorize_and_write(&mut stdout, color(Color::Yellow), &format!("{}:", interface.index))?;
 
         // Name - second column
         colorize_and_write(
             &mut stdout,
-            Color::Cyan,
+            color(Color::Cyan),
             &format!(
                 " Name: {name:<max_name_length$}",
                 name = interface.name,
                 max_name_length = max_length(interfaces, |iface| iface.name.len())
             ),
-        );
+        )?;
 
         colorize_and_write(
             &mut stdout,
-            Color::White,
+            color(Color::White),
             &format!(
                 " Mac: [{mac:<max_mac_length$}]",
                 mac = interface.mac.unwrap_or(MacAddr::zero()).to_string(),
@@ -58,11 +71,11 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
                     iface.mac.map_or(0, |mac| mac.to_string().len())
                 })
             ),
-        );
+        )?;
 
         colorize_and_write(
             &mut stdout,
-            Color::Magenta,
+            color(Color::Magenta),
             &format!(
                 " IPv4: [{ipv4:<max_ipv4_length$}]",
                 ipv4 = interface
@@ -72,13 +85,13 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
                     .map(|ip| ip.to_string())
                     .collect::<Vec<_>>()
                     .join(", "),
-                max_ipv4_length = max_length(interfaces, get_max_ipv4_length)
+                max_ipv4_length = max_length(interfaces, |iface| get_max_ipv4_length(iface))
             ),
-        );
+        )?;
 
         colorize_and_write(
             &mut stdout,
-            Color::Yellow,
+            color(Color::Yellow),
             &format!(
                 " Ipv6: [{ipv6:<max_ipv6_length$}]",
                 ipv6 = interface
@@ -88,22 +101,162 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
                     .map(|ip| ip.to_string())
                     .collect::<Vec<_>>()
                     .join(", "),
-                max_ipv6_length = max_length(interfaces, get_max_ipv6_length)
+                max_ipv6_length = max_length(interfaces, |iface| get_max_ipv6_length(iface))
             ),
-        );
+        )?;
 
         colorize_and_write(
             &mut stdout,
-            Color::White,
+            color(Color::Green),
+            &format!(
+                " MTU: {mtu:<5} State: {state:<7}",
+                mtu = get_mtu(&interface.name).map_or(String::from("-"), |mtu| mtu.to_string()),
+                state = get_oper_state(&interface.name).unwrap_or_else(|| String::from("-"))
+            ),
+        )?;
+
+        colorize_and_write(
+            &mut stdout,
+            color(Color::White),
             &format!(" Flags: [{flags}]", flags = get_flags(interface).unwrap()),
-        );
+        )?;
+
+        stdout.reset()?;
+        writeln!(&mut stdout)?;
+    }
+    Ok(())
+}
+
+/// Displays the result of an ARP scan.
+///
+/// This function prints a header with the interfaces and source addresses used for the scan,
+/// a table with one row per reply (IP, MAC address, vendor, round-trip time and flags),
+/// and a summary footer with the number of hosts found, the packets sent and received, the
+/// elapsed time, the duplicate replies, the targets that never answered and the round-trip
+/// time distribution. Columns are as wide as their longest value.
+///
+/// # Parameters
+///
+/// - `report`: The `ScanReport` returned by `net::arp_scan`.
+///
+/// # Examples
+///
+/// ```
+/// use plain::show_scan_report;
+///
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// show_scan_report(&report)?;
+/// ```
+pub fn show_scan_report(report: &ScanReport) -> Result<(), std::io::Error> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    write_scan_report(&mut stdout, report)
+}
+
+/// Writes the result of an ARP scan to `writer`, see `show_scan_report`.
+pub fn write_scan_report<W: WriteColor>(writer: &mut W, report: &ScanReport) -> Result<(), std::io::Error> {
+    for segment in &report.segments {
+        colorize_and_write(
+            writer,
+            Color::Green,
+            &format!(
+                "Interface: {}, MAC: {}, IPv4: {}, Network: {}\n",
+                segment.interface,
+                segment.source_mac.unwrap_or(MacAddr::zero()),
+                segment.source_ip,
+                segment.network
+            ),
+        )?;
+    }
 
-        stdout.reset().unwrap();
-        writeln!(&mut stdout).unwrap();
+    let hosts = &report.hosts;
+    let ip_length = max_length(hosts, |host| host.ip.to_string().len()).max("IP".len());
+    let mac_length = max_length(hosts, |host| host.mac.to_string().len()).max("MAC".len());
+    let vendor_length = max_length(hosts, |host| get_vendor(host).len()).max("Vendor".len());
+    let rtt_length = max_length(hosts, |host| get_rtt(host).len()).max("RTT".len());
+
+    if !hosts.is_empty() {
+        colorize_and_write(
+            writer,
+            Color::White,
+            &format!(
+                "{:<ip_length$} {:<mac_length$} {:<vendor_length$} {:>rtt_length$} Flags\n",
+                "IP", "MAC", "Vendor", "RTT"
+            ),
+        )?;
     }
+
+    for host in hosts {
+        colorize_and_write(writer, Color::Magenta, &format!("{:<ip_length$}", host.ip.to_string()))?;
+        colorize_and_write(writer, Color::White, &format!(" {:<mac_length$}", host.mac.to_string()))?;
+        colorize_and_write(writer, Color::Cyan, &format!(" {:<vendor_length$}", get_vendor(host)))?;
+        colorize_and_write(writer, Color::Yellow, &format!(" {:>rtt_length$}", get_rtt(host)))?;
+        let flags = get_host_flags(host);
+        if !flags.is_empty() {
+            colorize_and_write(writer, Color::Red, &format!(" {}", flags))?;
+        }
+        writer.reset()?;
+        writeln!(writer)?;
+    }
+
+    let statistics = ScanStatistics::from(report);
+    colorize_and_write(
+        writer,
+        Color::Green,
+        &format!(
+            "{} hosts found, {} packets sent ({:.1} packets/s), {} packets received in {:.3}s\n",
+            hosts.iter().filter(|host| !host.duplicate).count(),
+            statistics.packets_sent,
+            statistics.send_rate,
+            statistics.replies,
+            statistics.duration.as_secs_f64()
+        ),
+    )?;
+    colorize_and_write(
+        writer,
+        Color::Green,
+        &format!(
+            "{} duplicate replies, {} unsolicited replies, {} of {} targets never answered\n",
+            statistics.duplicates, statistics.unsolicited, statistics.unanswered, statistics.targets
+        ),
+    )?;
+    if let Some(rtt) = statistics.rtt {
+        colorize_and_write(
+            writer,
+            Color::Green,
+            &format!(
+                "RTT min/avg/max/p95: {:.3}/{:.3}/{:.3}/{:.3} ms\n",
+                as_millis(rtt.min),
+                as_millis(rtt.avg),
+                as_millis(rtt.max),
+                as_millis(rtt.p95)
+            ),
+        )?;
+    }
+    writer.reset()?;
     Ok(())
 }
 
+fn get_vendor(host: &DiscoveredHost) -> &str {
+    host.vendor.as_deref().unwrap_or("(Unknown)")
+}
+
+fn get_rtt(host: &DiscoveredHost) -> String {
+    host.rtt
+        .map(|rtt| format!("{:.3} ms", rtt.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| String::from("-"))
+}
+
+/// Gets the flags of a discovered host: `DUP` for a duplicate reply and `UNSOL` for an
+/// unsolicited one.
+fn get_host_flags(host: &DiscoveredHost) -> String {
+    [(host.duplicate, "DUP"), (host.unsolicited, "UNSOL")]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 ///
 /// # Arguments
 ///
@@ -111,9 +264,9 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
 /// * `color` - The color to be applied to the content.
 /// * `content` - The text content to be colorized and written.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if setting the color or writing to the writer fails.
+/// Returns the error of setting the color or writing to the writer, e.g. a closed pipe.
 ///
 /// # Examples
 ///
@@ -122,23 +275,21 @@ pub fn show_list_interfaces(interfaces: &[&NetworkInterface]) -> Result<(), std:
 /// let mut stdout = StandardStream::stdout(ColorChoice::Always);
 /// colorize_and_write(&mut stdout, Color::Green, "This is green text");
 /// ```
-fn colorize_and_write<W: WriteColor>(writer: &mut W, color: Color, content: &str) {
-    writer
-        .set_color(ColorSpec::new().set_fg(Some(color)))
-        .unwrap();
-    write!(writer, "{}", content).unwrap();
+fn colorize_and_write<W: WriteColor>(writer: &mut W, color: Color, content: &str) -> Result<(), std::io::Error> {
+    writer.set_color(ColorSpec::new().set_fg(Some(color)))?;
+    write!(writer, "{}", content)
 }
 
-/// Finds the maximum length of a property among a collection of network interfaces.
+/// Finds the maximum length of a property among a collection of items (network interfaces, discovered hosts).
 ///
 /// # Arguments
 ///
-/// * `interfaces` - A vector of references to `NetworkInterface` instances.
-/// * `property` - A closure that takes a reference to a `NetworkInterface` and returns a property's length.
+/// * `items` - A slice of the items to measure.
+/// * `property` - A closure that takes a reference to an item and returns a property's length.
 ///
 /// # Returns
 ///
-/// The maximum length among the specified property for all network interfaces. Returns 0 if the vector is empty.
+/// The maximum length among the specified property for all items. Returns 0 if the slice is empty.
 ///
 /// # Examples
 ///
@@ -146,13 +297,13 @@ fn colorize_and_write<W: WriteColor>(writer: &mut W, color: Color, content: &str
 /// let interfaces = vec![/* ... */];
 /// let max_name_length = max_length(&interfaces, |iface| iface.name.len());
 /// ```
-fn max_length<F: Fn(&NetworkInterface) -> usize>(
-    interfaces: &[&NetworkInterface],
+fn max_length<T, F: Fn(&T) -> usize>(
+    items: &[T],
     property: F,
 ) -> usize {
-    interfaces
+    items
         .iter()
-        .map(|iface| property(iface))
+        .map(property)
         .max()
         .unwrap_or(0)
 }
@@ -178,61 +329,8 @@ fn max_length<F: Fn(&NetworkInterface) -> usize>(
 /// }
 /// ```
 fn get_flags(interface: &NetworkInterface) -> Result<String, String> {
-    const FLAGS: [&str; 8] = [
-        "UP",
-        "BROADCAST",
-        "LOOPBACK",
-        "POINTOPOINT",
-        "MULTICAST",
-        "RUNNING",
-        "DORMANT",
-        "LOWERUP",
-    ];
     let flags = if interface.flags > 0 {
-        #[cfg(any(target_os = "linux", target_os = "android"))]
-            let rets = [
-            interface.is_up(),
-            interface.is_broadcast(),
-            interface.is_loopback(),
-            interface.is_point_to_point(),
-            interface.is_multicast(),
-            interface.is_running(),
-            interface.is_dormant(),
-            interface.is_lower_up(),
-        ];
-        #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
-            let rets = [
-            interface.is_up(),
-            interface.is_broadcast(),
-            interface.is_loopback(),
-            interface.is_point_to_point(),
-            interface.is_multicast(),
-            interface.is_running(),
-            false,
-            false,
-        ];
-        #[cfg(not(unix))]
-            let rets = [
-            interface.is_up(),
-            interface.is_broadcast(),
-            interface.is_loopback(),
-            interface.is_point_to_point(),
-            interface.is_multicast(),
-            false,
-            false,
-            false,
-        ];
-
-        format!(
-            "{:X}<{}>",
-            interface.flags,
-            rets.iter()
-                .zip(FLAGS.iter())
-                .filter(|&(ret, _)| ret == &true)
-                .map(|(_, name)| name.to_string())
-                .collect::<Vec<String>>()
-                .join(",")
-        )
+        format!("{:X}<{}>", interface.flags, get_flag_names(interface).join(","))
     } else {
         format!("{:X}", interface.flags)
     };
@@ -259,3 +357,61 @@ fn get_max_ipv6_length(interface: &NetworkInterface) -> usize {
         .max()
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use termcolor::NoColor;
+
+    use crate::report::fixtures::sample_report;
+
+    use super::*;
+
+    #[test]
+    fn test_write_scan_report_table() {
+        let mut report = sample_report();
+        report.hosts[0].vendor = Some(String::from("Acme"));
+        let mut output = NoColor::new(Vec::new());
+
+        write_scan_report(&mut output, &report).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output.into_inner()).unwrap(),
+            "Interface: eth0, MAC: 02:00:00:00:00:01, IPv4: 192.168.0.10, Network: 192.168.0.0/24\n\
+             IP          MAC               Vendor         RTT Flags\n\
+             192.168.0.1 00:11:22:33:44:55 Acme      1.500 ms\n\
+             192.168.0.1 00:11:22:33:44:66 (Unknown)        - DUP\n\
+             1 hosts found, 254 packets sent (506.0 packets/s), 2 packets received in 2.500s\n\
+             1 duplicate replies, 0 unsolicited replies, 253 of 254 targets never answered\n\
+             RTT min/avg/max/p95: 1.500/1.500/1.500/1.500 ms\n"
+        );
+    }
+
+    #[test]
+    fn test_write_scan_report_returns_write_errors() {
+        struct ClosedPipe;
+
+        impl Write for ClosedPipe {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let result = write_scan_report(&mut NoColor::new(ClosedPipe), &sample_report());
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_get_host_flags() {
+        let mut host = sample_report().hosts.remove(0);
+        assert_eq!(get_host_flags(&host), "");
+
+        host.duplicate = true;
+        host.unsolicited = true;
+        assert_eq!(get_host_flags(&host), "DUP,UNSOL");
+    }
+}
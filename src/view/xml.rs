@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::report::{DiscoveredHost, ScanReport};
+use crate::stats::ScanStatistics;
+use crate::view::document::{as_millis, format_time};
+
+/// Version of the nmap XML output format the document follows.
+const NMAP_XML_OUTPUT_VERSION: &str = "1.05";
+
+/// Writes a scan report as an nmap-compatible XML document.
+///
+/// Every answering IP address becomes a `<host>` in state `up` with reason
+/// `arp-response`, holding an `ipv4` and a `mac` address (with its vendor, if known).
+/// Duplicate replies are left out since nmap reports each host once, but an address that
+/// answered on several interfaces is a different host on each of them. The scan statistics
+/// have no nmap equivalent and are written as a comment in `<runstats>`, so they do not
+/// break existing parsers. No `<scaninfo>` is written: the nmap DTD has no scan type for ARP.
+/// Neither is `<times>`, whose RTT variance and timeout nmap derives from its own probes.
+///
+/// # Parameters
+///
+/// - `writer`: The destination of the XML document.
+/// - `report`: The `ScanReport` returned by `net::arp_scan`.
+/// - `args`: The command line the scan was run with, written in the `args` attribute.
+///
+/// # Examples
+///
+/// ```
+/// use xml::write_scan_report;
+///
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// let args: Vec<String> = std::env::args().collect();
+/// write_scan_report(&mut std::io::stdout(), &report, &args)?;
+/// ```
+pub fn write_scan_report<W: Write>(writer: &mut W, report: &ScanReport, args: &[String]) -> Result<(), io::Error> {
+    let statistics = ScanStatistics::from(report);
+    let mut seen = HashSet::new();
+    let hosts: Vec<&DiscoveredHost> = report
+        .hosts
+        .iter()
+        .filter(|host| seen.insert((host.interface.as_str(), host.ip)))
+        .collect();
+    let total = (report.targets as usize).max(hosts.len());
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<!DOCTYPE nmaprun>")?;
+    writeln!(
+        writer,
+        r#"<nmaprun scanner="arp_scan_rust" args="{}" start="{}" startstr="{}" version="{}" xmloutputversion="{}">"#,
+        escape(&args.join(" ")),
+        epoch_seconds(report.started_at),
+        format_time(report.started_at),
+        env!("CARGO_PKG_VERSION"),
+        NMAP_XML_OUTPUT_VERSION
+    )?;
+    writeln!(writer, r#"<verbose level="0"/>"#)?;
+    writeln!(writer, r#"<debugging level="0"/>"#)?;
+
+    for host in hosts.iter() {
+        let reply_time = epoch_seconds(host.reply_time);
+        writeln!(writer, r#"<host starttime="{}" endtime="{}">"#, reply_time, reply_time)?;
+        writeln!(writer, r#"<status state="up" reason="arp-response" reason_ttl="0"/>"#)?;
+        writeln!(writer, r#"<address addr="{}" addrtype="ipv4"/>"#, host.ip)?;
+        let mac = host.mac.to_string().to_uppercase();
+        match &host.vendor {
+            Some(vendor) => writeln!(
+                writer,
+                r#"<address addr="{}" addrtype="mac" vendor="{}"/>"#,
+                mac,
+                escape(vendor)
+            )?,
+            None => writeln!(writer, r#"<address addr="{}" addrtype="mac"/>"#, mac)?,
+        }
+        writeln!(writer, "<hostnames>\n</hostnames>")?;
+        writeln!(writer, "</host>")?;
+    }
+
+    let elapsed = report
+        .finished_at
+        .duration_since(report.started_at)
+        .unwrap_or_default()
+        .as_secs_f64();
+    writeln!(writer, "<runstats>")?;
+    write!(
+        writer,
+        "<!-- arp_scan_rust statistics: {} packets sent ({:.1} packets/s), {} replies, {} duplicates, \
+         {} unsolicited, {} of {} targets never answered",
+        statistics.packets_sent,
+        statistics.send_rate,
+        statistics.replies,
+        statistics.duplicates,
+        statistics.unsolicited,
+        statistics.unanswered,
+        statistics.targets
+    )?;
+    if let Some(rtt) = statistics.rtt {
+        write!(
+            writer,
+            ", RTT min/avg/max/p95 {:.3}/{:.3}/{:.3}/{:.3} ms",
+            as_millis(rtt.min),
+            as_millis(rtt.avg),
+            as_millis(rtt.max),
+            as_millis(rtt.p95)
+        )?;
+    }
+    writeln!(writer, " -->")?;
+    writeln!(
+        writer,
+        r#"<finished time="{}" timestr="{}" elapsed="{:.2}" summary="{} IP addresses ({} hosts up) scanned in {:.2} seconds" exit="success"/>"#,
+        epoch_seconds(report.finished_at),
+        format_time(report.finished_at),
+        elapsed,
+        total,
+        hosts.len(),
+        elapsed
+    )?;
+    writeln!(
+        writer,
+        r#"<hosts up="{}" down="{}" total="{}"/>"#,
+        hosts.len(),
+        total - hosts.len(),
+        total
+    )?;
+    writeln!(writer, "</runstats>")?;
+    writeln!(writer, "</nmaprun>")?;
+    writer.flush()
+}
+
+/// Displays the result of an ARP scan as nmap XML on the standard output.
+pub fn show_scan_report(report: &ScanReport) -> Result<(), io::Error> {
+    let args: Vec<String> = std::env::args().collect();
+    write_scan_report(&mut io::stdout().lock(), report, &args)
+}
+
+fn epoch_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+/// Escapes the characters that are not allowed in an XML attribute value.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::fixtures::sample_report;
+
+    use super::*;
+
+    #[test]
+    fn test_write_scan_report_nmap_hosts() {
+        let mut report = sample_report();
+        report.hosts[0].vendor = Some(String::from("Acme & Sons"));
+        let mut output = Vec::new();
+
+        let args = ["arp_scan_rust", "--network", "192.168.0.0/24", "--oui-file", "a&b.txt"].map(String::from);
+
+        write_scan_report(&mut output, &report, &args).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE nmaprun>\n<nmaprun scanner=\"arp_scan_rust\""));
+        assert!(output.contains(r#" args="arp_scan_rust --network 192.168.0.0/24 --oui-file a&amp;b.txt" "#));
+        assert!(output.contains(r#" start="1700000000" "#));
+        assert!(output.contains("\n<verbose level=\"0\"/>\n"));
+        assert!(!output.contains("<scaninfo"));
+        assert_eq!(output.matches("<host ").count(), 1);
+        assert!(output.contains(r#"<status state="up" reason="arp-response" reason_ttl="0"/>"#));
+        assert!(output.contains(r#"<address addr="192.168.0.1" addrtype="ipv4"/>"#));
+        assert!(output.contains(r#"<address addr="00:11:22:33:44:55" addrtype="mac" vendor="Acme &amp; Sons"/>"#));
+        assert!(!output.contains("<times"));
+        assert!(output.contains(r#"<hosts up="1" down="253" total="254"/>"#));
+        assert!(output.contains(
+            "<!-- arp_scan_rust statistics: 254 packets sent (506.0 packets/s), 2 replies, 1 duplicates, \
+             0 unsolicited, 253 of 254 targets never answered, RTT min/avg/max/p95 1.500/1.500/1.500/1.500 ms -->"
+        ));
+        assert!(output.ends_with("</runstats>\n</nmaprun>\n"));
+    }
+
+    #[test]
+    fn test_write_scan_report_keeps_same_address_on_other_interface() {
+        let mut other = sample_report();
+        other.interface = String::from("eth1");
+        other.segments[0].interface = String::from("eth1");
+        for host in &mut other.hosts {
+            host.interface = String::from("eth1");
+        }
+        let report = ScanReport::merge(vec![sample_report(), other]).unwrap();
+        let mut output = Vec::new();
+
+        write_scan_report(&mut output, &report, &[]).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<host ").count(), 2);
+    }
+
+    #[test]
+    fn test_escape_attribute_value() {
+        assert_eq!(escape(r#"<"a" & 'b'>"#), "&lt;&quot;a&quot; &amp; &apos;b&apos;&gt;");
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+
+use crate::report::ScanReport;
+use crate::view::document::{InterfaceRecord, InterfacesDocument, ScanDocument};
+
+/// Writes a scan report as a YAML document.
+///
+/// The document has the same structure as the JSON output, see
+/// `view::document::ScanDocument`.
+///
+/// # Parameters
+///
+/// - `writer`: The destination of the YAML document.
+/// - `report`: The `ScanReport` returned by `net::arp_scan`.
+///
+/// # Examples
+///
+/// ```
+/// use yaml::write_scan_report;
+///
+/// let report = net::arp_scan(&mut transport, &options)?;
+/// write_scan_report(&mut std::io::stdout(), &report)?;
+/// ```
+pub fn write_scan_report<W: Write>(writer: &mut W, report: &ScanReport) -> Result<(), io::Error> {
+    serde_norway::to_writer(&mut *writer, &ScanDocument::from(report))
+        .map_err(io::Error::other)?;
+    writer.flush()
+}
+
+/// Displays the result of an ARP scan as YAML on the standard output.
+pub fn show_scan_report(report: &ScanReport) -> Result<(), io::Error> {
+    write_scan_report(&mut io::stdout().lock(), report)
+}
+
+/// Writes a list of network interfaces as a YAML document, see
+/// `view::document::InterfacesDocument`.
+pub fn write_interfaces<W: Write>(writer: &mut W, interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    serde_norway::to_writer(&mut *writer, &InterfacesDocument::from(interfaces))
+        .map_err(io::Error::other)?;
+    writer.flush()
+}
+
+/// Displays a list of network interfaces as YAML on the standard output.
+pub fn show_interfaces(interfaces: &[InterfaceRecord]) -> Result<(), io::Error> {
+    write_interfaces(&mut io::stdout().lock(), interfaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_norway::Value;
+
+    use crate::report::fixtures::sample_report;
+    use crate::view::document::SCHEMA_VERSION;
+
+    use super::*;
+
+    #[test]
+    fn test_write_scan_report_yaml_document() {
+        let mut output = Vec::new();
+        write_scan_report(&mut output, &sample_report()).unwrap();
+
+        let document: Value = serde_norway::from_slice(&output).unwrap();
+        assert_eq!(document["schema_version"].as_str(), Some(SCHEMA_VERSION));
+        assert_eq!(document["scan"]["interface"].as_str(), Some("eth0"));
+        assert_eq!(document["scan"]["packets_received"].as_u64(), Some(2));
+        assert_eq!(document["hosts"][0]["ip"].as_str(), Some("192.168.0.1"));
+        assert_eq!(document["hosts"][0]["mac"].as_str(), Some("00:11:22:33:44:55"));
+        assert_eq!(document["hosts"][1]["duplicate"].as_bool(), Some(true));
+        assert!(document["hosts"][1]["rtt_ms"].is_null());
+    }
+
+    #[test]
+    fn test_yaml_and_json_share_the_same_structure() {
+        let mut yaml = Vec::new();
+        write_scan_report(&mut yaml, &sample_report()).unwrap();
+        let mut json = Vec::new();
+        crate::view::json::write_scan_report(&mut json, &sample_report()).unwrap();
+
+        let from_yaml: serde_json::Value = serde_norway::from_slice(&yaml).unwrap();
+        let from_json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(from_yaml, from_json);
+    }
+}